@@ -1,14 +1,77 @@
 use std::borrow::Cow;
+use std::fmt;
 
+use crate::{
+    parse::{Module, TypeError},
+    PrimitiveTy,
+};
+
+mod x86_64;
+
+/// Lowers a whole [`Module`] to GNU assembler source for x86-64.
+pub fn compile_module(module: &Module) -> Result<String, CodegenError> {
+    x86_64::compile_module(module)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CodegenError {
+    Type(TypeError),
+    InvalidLiteral(String),
+    /// An integer literal doesn't fit in the type it is used as.
+    LiteralOutOfRange {
+        value: String,
+        ty: PrimitiveTy,
+    },
+    DuplicateFunction(String),
+    /// A function with a return type can reach its end without returning.
+    MissingReturn(String),
+    /// The module contains a [`Statement::Error`](crate::parse::Statement::Error).
+    ErrorNode,
+    /// A `break` or `continue` outside of any loop.
+    OutsideLoop(&'static str),
+    /// A `break` or `continue` naming a label no enclosing loop has.
+    UnknownLabel(String),
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::Type(e) => write!(f, "{}", e),
+            CodegenError::InvalidLiteral(value) => write!(f, "invalid literal `{}`", value),
+            CodegenError::LiteralOutOfRange { value, ty } => {
+                write!(f, "literal `{}` does not fit in type `{}`", value, ty)
+            }
+            CodegenError::DuplicateFunction(name) => {
+                write!(f, "function `{}` is defined more than once", name)
+            }
+            CodegenError::MissingReturn(name) => {
+                write!(f, "function `{}` does not always return a value", name)
+            }
+            CodegenError::ErrorNode => write!(f, "cannot generate code for a statement that failed to parse"),
+            CodegenError::OutsideLoop(keyword) => write!(f, "`{}` outside of a loop", keyword),
+            CodegenError::UnknownLabel(label) => write!(f, "use of undeclared label `'{}`", label),
+            CodegenError::Unsupported(what) => write!(f, "{} is not supported yet", what),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+impl From<TypeError> for CodegenError {
+    fn from(e: TypeError) -> Self {
+        CodegenError::Type(e)
+    }
+}
+
+#[derive(Default)]
 pub struct Generator {
     asm: Vec<String>,
 }
 
 impl Generator {
     pub fn new() -> Self {
-        Generator {
-            asm: Vec::new()
-        }
+        Self::default()
     }
 
     pub fn raw<'a>(&mut self, line: impl Into<Cow<'a, str>>) {
@@ -23,9 +86,9 @@ impl Generator {
         self.raw(format!("{}: {}", name.into(), value.into()));
     }
 
-    pub fn exit(&mut self, code: u32) {
-        self.raw(format!("mov ${}, %rdi", code));
-        self.raw("call exit");
+    /// Appends all lines of `other` to this generator.
+    pub fn append(&mut self, other: Generator) {
+        self.asm.extend(other.asm);
     }
 
     pub fn full_raw(&self) -> String {
@@ -33,4 +96,4 @@ impl Generator {
         s.push('\n');
         s
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1402 @@
+use std::collections::HashMap;
+
+use crate::{
+    parse::{
+        BinaryOperationTy, Call, ControlFlow, Expression, Function, Literal, Module, Operation,
+        operand_type, ParsedLiteral, Scope, Signature, Statement, TypeError, UnaryOperationTy,
+    },
+    FloatTy, IntegerTy, PrimitiveTy,
+};
+
+use super::{CodegenError, Generator};
+
+pub fn compile_module(module: &Module) -> Result<String, CodegenError> {
+    let mut gen = Generator::new();
+    gen.raw(".global main");
+    gen.raw(".text");
+
+    let mut strings = Vec::new();
+    let mut signatures = HashMap::new();
+    for func in &module.functions {
+        let signature = Signature {
+            arguments: func
+                .arguments
+                .iter()
+                .map(|argument| argument.ty.resolve())
+                .collect::<Result<_, _>>()?,
+            return_type: match &func.return_type {
+                Some(ty) => ty.resolve()?,
+                None => PrimitiveTy::Void,
+            },
+        };
+        if signatures.insert(func.name, signature).is_some() {
+            return Err(CodegenError::DuplicateFunction(func.name.to_owned()));
+        }
+    }
+
+    for func in &module.functions {
+        gen.append(FunctionGenerator::new(&signatures, &mut strings, func).generate(func)?);
+    }
+
+    if !strings.is_empty() {
+        gen.raw(".section .rodata");
+        for (i, value) in strings.iter().enumerate() {
+            gen.label(string_label(i));
+            gen.raw(format!(".asciz \"{}\"", escape_asm_string(value)));
+        }
+    }
+
+    // Tell the linker we don't need an executable stack.
+    gen.raw(".section .note.GNU-stack,\"\",@progbits");
+    Ok(gen.full_raw())
+}
+
+/// Names of one general purpose register as 8, 16, 32 and 64-bit operands.
+type Register = [&'static str; 4];
+
+const RAX: Register = ["%al", "%ax", "%eax", "%rax"];
+const RCX: Register = ["%cl", "%cx", "%ecx", "%rcx"];
+const RDX: Register = ["%dl", "%dx", "%edx", "%rdx"];
+
+/// System V AMD64 integer argument registers.
+const ARGUMENT_REGISTERS: [Register; 6] = [
+    ["%dil", "%di", "%edi", "%rdi"],
+    ["%sil", "%si", "%esi", "%rsi"],
+    RDX,
+    RCX,
+    ["%r8b", "%r8w", "%r8d", "%r8"],
+    ["%r9b", "%r9w", "%r9d", "%r9"],
+];
+
+/// Number of `%xmm` registers System V AMD64 passes float arguments in.
+const FLOAT_ARGUMENT_REGISTERS: usize = 8;
+
+/// Where a System V AMD64 argument is passed.
+#[derive(Debug, Copy, Clone)]
+enum ArgumentLocation {
+    General(Register),
+    /// `%xmmN` for float arguments.
+    Float(usize),
+    /// The Nth 8-byte slot above the return address, for arguments that don't fit
+    /// in registers.
+    Stack(usize),
+}
+
+/// Assigns locations to arguments of the given types. Integers and floats are
+/// numbered separately, so `fn(i32, f64, i32)` uses `%edi`, `%xmm0` and `%esi`,
+/// and whatever doesn't fit in registers goes on the stack in order.
+fn argument_locations(types: &[PrimitiveTy]) -> Vec<ArgumentLocation> {
+    let (mut general, mut float, mut stack) = (0, 0, 0);
+    types
+        .iter()
+        .map(|ty| {
+            let register = if ty.is_float() {
+                float += 1;
+                (float <= FLOAT_ARGUMENT_REGISTERS).then_some(ArgumentLocation::Float(float - 1))
+            } else {
+                general += 1;
+                ARGUMENT_REGISTERS
+                    .get(general - 1)
+                    .map(|&register| ArgumentLocation::General(register))
+            };
+            register.unwrap_or_else(|| {
+                stack += 1;
+                ArgumentLocation::Stack(stack - 1)
+            })
+        })
+        .collect()
+}
+
+/// The name of `register` as an operand of `size` bytes.
+fn sized(register: Register, size: u32) -> &'static str {
+    match size {
+        1 => register[0],
+        2 => register[1],
+        4 => register[2],
+        _ => register[3],
+    }
+}
+
+/// Instruction suffix for operands of `size` bytes.
+fn suffix(size: u32) -> char {
+    match size {
+        1 => 'b',
+        2 => 'w',
+        4 => 'l',
+        _ => 'q',
+    }
+}
+
+/// Label of the `n`th entry in the string pool.
+fn string_label(n: usize) -> String {
+    format!(".Lstr_{}", n)
+}
+
+/// Escapes `value` for a quoted assembler string, writing anything but
+/// printable ASCII as octal bytes.
+fn escape_asm_string(value: &str) -> String {
+    let mut out = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            0x20..=0x7e => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out
+}
+
+fn function_label(name: &str) -> String {
+    if name == "main" {
+        "main".to_owned()
+    } else {
+        format!("deltafunc_{}", name)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct LocalVar {
+    /// Offset below `%rbp`.
+    stack_index: u32,
+    ty: PrimitiveTy,
+}
+
+impl LocalVar {
+    fn operand(&self) -> String {
+        format!("-{}(%rbp)", self.stack_index)
+    }
+}
+
+/// Size of `ty` in memory.
+fn size_of(ty: PrimitiveTy) -> u32 {
+    match ty.defaulted() {
+        PrimitiveTy::Integer(int) => int.size(),
+        PrimitiveTy::Float(float) => float.size(),
+        PrimitiveTy::Boolean => 1,
+        PrimitiveTy::Char => 4,
+        PrimitiveTy::Str => 8,
+        PrimitiveTy::Number | PrimitiveTy::Decimal | PrimitiveTy::Void => 0,
+    }
+}
+
+/// Size of `ty` while held in a register. Integers narrower than 32 bits are
+/// kept sign or zero-extended to 32 bits so they can use the full instruction set.
+/// Floats are held as their bit pattern and only moved to `%xmm` registers for
+/// arithmetic. Strings are held as a pointer to their NUL-terminated bytes.
+fn operand_size(ty: PrimitiveTy) -> Result<u32, CodegenError> {
+    match ty.defaulted() {
+        PrimitiveTy::Integer(int) => Ok(int.size().max(4)),
+        PrimitiveTy::Float(float) => Ok(float.size()),
+        PrimitiveTy::Boolean => Ok(1),
+        PrimitiveTy::Char => Ok(4),
+        PrimitiveTy::Str => Ok(8),
+        PrimitiveTy::Number | PrimitiveTy::Decimal | PrimitiveTy::Void => {
+            Err(CodegenError::Unsupported("values of type Void"))
+        }
+    }
+}
+
+/// The `ss`/`sd` suffix of SSE instructions on `float`.
+fn float_suffix(float: FloatTy) -> &'static str {
+    match float {
+        FloatTy::F32 => "ss",
+        FloatTy::F64 => "sd",
+    }
+}
+
+fn is_signed(ty: PrimitiveTy) -> bool {
+    match ty.defaulted() {
+        PrimitiveTy::Integer(int) => int.is_signed(),
+        _ => false,
+    }
+}
+
+fn expect_type(expected: PrimitiveTy, found: PrimitiveTy) -> Result<(), CodegenError> {
+    if !found.coerces_to(expected) {
+        return Err(TypeError::Mismatch { expected, found }.into());
+    }
+    Ok(())
+}
+
+/// Jump targets of a loop enclosing the statement being generated.
+struct LoopLabels {
+    name: Option<String>,
+    continue_label: String,
+    break_label: String,
+}
+
+struct FunctionGenerator<'a, 'g> {
+    /// Code following the prologue, which is only emitted once the frame size is known.
+    gen: Generator,
+    signatures: &'g HashMap<&'a str, Signature>,
+    /// Values of the string literals in the module, deduplicated.
+    strings: &'g mut Vec<String>,
+    label: String,
+    is_main: bool,
+    return_type: PrimitiveTy,
+    /// Variables visible in each enclosing block, innermost last.
+    scopes: Vec<HashMap<&'a str, LocalVar>>,
+    stack_size: u32,
+    /// Number of 8-byte values currently pushed on top of the frame.
+    stack_depth: usize,
+    branch_counter: usize,
+    /// Enclosing loops, innermost last.
+    loops: Vec<LoopLabels>,
+}
+
+impl<'a, 'g> Scope for FunctionGenerator<'a, 'g> {
+    fn variable(&self, name: &str) -> Option<PrimitiveTy> {
+        self.lookup(name).map(|l| l.ty)
+    }
+
+    fn function(&self, name: &str) -> Option<&Signature> {
+        self.signatures.get(name)
+    }
+}
+
+impl<'a, 'g> FunctionGenerator<'a, 'g> {
+    fn new(
+        signatures: &'g HashMap<&'a str, Signature>,
+        strings: &'g mut Vec<String>,
+        func: &Function,
+    ) -> Self {
+        FunctionGenerator {
+            gen: Generator::new(),
+            signatures,
+            strings,
+            label: function_label(func.name),
+            is_main: func.name == "main",
+            return_type: PrimitiveTy::Void,
+            scopes: Vec::new(),
+            stack_size: 0,
+            stack_depth: 0,
+            branch_counter: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    fn generate(mut self, func: &'a Function<'a>) -> Result<Generator, CodegenError> {
+        let signature = &self.signatures[func.name];
+        self.return_type = signature.return_type;
+        self.scopes.push(HashMap::new());
+        for ((argument, &ty), location) in func
+            .arguments
+            .iter()
+            .zip(&signature.arguments)
+            .zip(argument_locations(&signature.arguments))
+        {
+            let local = self.allocate_slot(ty)?;
+            self.declare(argument.name, local);
+            let size = size_of(ty);
+            match (location, ty) {
+                (ArgumentLocation::Float(n), PrimitiveTy::Float(float)) => self.gen.raw(format!(
+                    "mov{} %xmm{}, {}",
+                    float_suffix(float),
+                    n,
+                    local.operand()
+                )),
+                (ArgumentLocation::General(register), _) => self.gen.raw(format!(
+                    "mov{} {}, {}",
+                    suffix(size),
+                    sized(register, size),
+                    local.operand()
+                )),
+                // Stack arguments start above the saved `%rbp` and the return address.
+                (ArgumentLocation::Stack(n), _) => {
+                    self.gen.raw(format!(
+                        "mov{} {}(%rbp), {}",
+                        suffix(size),
+                        16 + 8 * n,
+                        sized(RAX, size)
+                    ));
+                    self.store(local);
+                }
+                _ => unreachable!("float arguments are passed in %xmm registers"),
+            }
+        }
+
+        for stmt in &func.body {
+            self.gen_statement(stmt)?;
+        }
+
+        if !always_returns(&func.body) && self.return_type != PrimitiveTy::Void {
+            return Err(CodegenError::MissingReturn(func.name.to_owned()));
+        }
+        self.gen.label(self.return_label());
+        // A void `main` exits successfully, whether it falls off the end or returns early.
+        if self.is_main && self.return_type == PrimitiveTy::Void {
+            self.gen.raw("xor %eax, %eax");
+        }
+        self.gen.raw("leave");
+        self.gen.raw("ret");
+
+        let mut out = Generator::new();
+        out.label(&self.label);
+        out.raw("push %rbp");
+        out.raw("mov %rsp, %rbp");
+        let frame_size = self.stack_size.next_multiple_of(16);
+        if frame_size > 0 {
+            out.raw(format!("sub ${}, %rsp", frame_size));
+        }
+        out.append(self.gen);
+        Ok(out)
+    }
+
+    fn return_label(&self) -> String {
+        format!(".L{}_return", self.label)
+    }
+
+    /// Reserves a stack slot for a value of type `ty` without naming it.
+    fn allocate_slot(&mut self, ty: PrimitiveTy) -> Result<LocalVar, CodegenError> {
+        let size = size_of(ty);
+        if size == 0 {
+            return Err(CodegenError::Unsupported("variables of type Void"));
+        }
+        self.stack_size = (self.stack_size + size).next_multiple_of(size);
+        Ok(LocalVar {
+            stack_index: self.stack_size,
+            ty,
+        })
+    }
+
+    /// Makes `local` visible as `name` until the end of the current block.
+    fn declare(&mut self, name: &'a str, local: LocalVar) {
+        self.scopes.last_mut().unwrap().insert(name, local);
+    }
+
+    fn lookup(&self, name: &str) -> Option<LocalVar> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    fn local(&self, name: &str) -> Result<LocalVar, CodegenError> {
+        self.lookup(name)
+            .ok_or_else(|| TypeError::UnknownVariable(name.to_owned()).into())
+    }
+
+    fn get_type(&self, expr: &Expression) -> Result<PrimitiveTy, CodegenError> {
+        Ok(expr.get_type(self)?)
+    }
+
+    /// Generates `body` in a new scope.
+    fn gen_block(&mut self, body: &'a [Statement<'a>]) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+        for stmt in body {
+            self.gen_statement(stmt)?;
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn gen_statement(&mut self, stmt: &'a Statement<'a>) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::Declaration {
+                name,
+                ty,
+                expression,
+            } => {
+                let found = self.get_type(expression)?;
+                let ty = match ty {
+                    Some(ty) => {
+                        let ty = ty.resolve()?;
+                        expect_type(ty, found)?;
+                        ty
+                    }
+                    None => found.defaulted(),
+                };
+                // The name is only declared afterwards, so the initializer still
+                // sees any variable it shadows.
+                let out = self.allocate_slot(ty)?;
+                self.gen_expr(expression, Some(out))?;
+                self.declare(name, out);
+                Ok(())
+            }
+            Statement::Expression(expr) => self.gen_expr(expr, None),
+            Statement::ControlFlow(ControlFlow::If {
+                condition,
+                body,
+                else_body,
+            }) => self.gen_if(condition, body, else_body.as_deref()),
+            Statement::ControlFlow(ControlFlow::While {
+                label,
+                condition,
+                body,
+            }) => self.gen_loop(*label, Some(condition), body),
+            Statement::ControlFlow(ControlFlow::Loop { label, body }) => {
+                self.gen_loop(*label, None, body)
+            }
+            Statement::ControlFlow(ControlFlow::For {
+                label,
+                variable,
+                start,
+                end,
+                inclusive,
+                body,
+            }) => self.gen_for(*label, variable, start, end, *inclusive, body),
+            Statement::ControlFlow(ControlFlow::Break(label)) => {
+                let target = self.loop_labels("break", *label)?.break_label.clone();
+                self.gen.raw(format!("jmp {}", target));
+                Ok(())
+            }
+            Statement::ControlFlow(ControlFlow::Continue(label)) => {
+                let target = self.loop_labels("continue", *label)?.continue_label.clone();
+                self.gen.raw(format!("jmp {}", target));
+                Ok(())
+            }
+            Statement::Return(value) => {
+                match value {
+                    Some(value) => {
+                        expect_type(self.return_type, self.get_type(value)?)?;
+                        self.gen_value_as(value, self.return_type)?;
+                        if self.return_type.is_float() {
+                            self.gen.raw("movq %rax, %xmm0");
+                        }
+                    }
+                    None => expect_type(self.return_type, PrimitiveTy::Void)?,
+                }
+                self.gen.raw(format!("jmp {}", self.return_label()));
+                Ok(())
+            }
+            Statement::Error => Err(CodegenError::ErrorNode),
+        }
+    }
+
+    fn gen_expr(&mut self, expr: &Expression, out: Option<LocalVar>) -> Result<(), CodegenError> {
+        match expr {
+            Expression::Literal(lit) if lit.ty == PrimitiveTy::Str => self.gen_store(expr, out),
+            Expression::Literal(lit) => {
+                if let Some(out) = out {
+                    expect_type(out.ty, lit.ty)?;
+                    let v = literal_value(lit, out.ty)?;
+                    // 64-bit stores only take sign-extended 32-bit immediates.
+                    if i32::try_from(v).is_err() && size_of(out.ty) == 8 {
+                        return self.gen_store(expr, Some(out));
+                    }
+                    self.gen.raw(format!(
+                        "mov{} ${}, {}",
+                        suffix(size_of(out.ty)),
+                        v,
+                        out.operand()
+                    ));
+                }
+                Ok(())
+            }
+            Expression::Operation(op) => match &**op {
+                Operation::Binary {
+                    left,
+                    ty: BinaryOperationTy::Assignment,
+                    right,
+                } => {
+                    if out.is_some() {
+                        return Err(CodegenError::Unsupported("using the value of an assignment"));
+                    }
+                    match left {
+                        Expression::Variable(var) => {
+                            let target = self.local(var)?;
+                            self.gen_expr(right, Some(target))
+                        }
+                        _ => Err(CodegenError::Unsupported(
+                            "assignment to anything but a variable",
+                        )),
+                    }
+                }
+                Operation::CompoundAssignment { target, ty, value } => {
+                    if out.is_some() {
+                        return Err(CodegenError::Unsupported("using the value of an assignment"));
+                    }
+                    // Type check the whole operation before generating any of it.
+                    self.get_type(expr)?;
+                    match target {
+                        Expression::Variable(var) => {
+                            let target = self.local(var)?;
+                            self.gen_compound_assignment(target, *ty, value)
+                        }
+                        _ => Err(CodegenError::Unsupported(
+                            "assignment to anything but a variable",
+                        )),
+                    }
+                }
+                _ => self.gen_store(expr, out),
+            },
+            Expression::Variable(_) | Expression::Call(_) => self.gen_store(expr, out),
+        }
+    }
+
+    /// Evaluates `expr` and stores the result in `out`, if any.
+    fn gen_store(&mut self, expr: &Expression, out: Option<LocalVar>) -> Result<(), CodegenError> {
+        match out {
+            Some(out) => {
+                expect_type(out.ty, self.get_type(expr)?)?;
+                self.gen_value_as(expr, out.ty)?;
+                self.store(out);
+            }
+            None => {
+                self.gen_value(expr)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the accumulator into `local`, truncating it to the local's size.
+    fn store(&mut self, local: LocalVar) {
+        let size = size_of(local.ty);
+        self.gen.raw(format!(
+            "mov{} {}, {}",
+            suffix(size),
+            sized(RAX, size),
+            local.operand()
+        ));
+    }
+
+    /// Loads `local` into `register`, extending it to its operand size.
+    fn load(&mut self, local: LocalVar, register: Register) -> Result<(), CodegenError> {
+        let size = size_of(local.ty);
+        let operand_size = operand_size(local.ty)?;
+        let instruction = if size == operand_size {
+            format!("mov{}", suffix(size))
+        } else {
+            let extend = if is_signed(local.ty) { "movs" } else { "movz" };
+            format!("{}{}{}", extend, suffix(size), suffix(operand_size))
+        };
+        self.gen.raw(format!(
+            "{} {}, {}",
+            instruction,
+            local.operand(),
+            sized(register, operand_size)
+        ));
+        Ok(())
+    }
+
+    /// Re-extends the accumulator after an operation on an integer narrower than
+    /// 32 bits, whose result may have overflowed into the upper bits.
+    fn extend(&mut self, ty: PrimitiveTy) {
+        let size = size_of(ty);
+        if let (PrimitiveTy::Integer(_), 1 | 2) = (ty, size) {
+            let extend = if is_signed(ty) { "movs" } else { "movz" };
+            self.gen.raw(format!(
+                "{}{}l {}, %eax",
+                extend,
+                suffix(size),
+                sized(RAX, size)
+            ));
+        }
+    }
+
+    /// Formats a variable or literal of type `ty` as an instruction operand,
+    /// `None` for anything that has to be evaluated into a register first.
+    fn operand(&self, expr: &Expression, ty: PrimitiveTy) -> Result<Option<String>, CodegenError> {
+        match expr {
+            // Narrow variables have to be extended before they can be used.
+            Expression::Variable(var) => {
+                let local = self.local(var)?;
+                if size_of(local.ty) == operand_size(local.ty)? {
+                    Ok(Some(local.operand()))
+                } else {
+                    Ok(None)
+                }
+            }
+            Expression::Literal(lit) if lit.ty == PrimitiveTy::Str => Ok(None),
+            Expression::Literal(lit) => {
+                let v = literal_value(lit, ty)?;
+                if i32::try_from(v).is_err() && operand_size(ty)? == 8 {
+                    Ok(None)
+                } else {
+                    Ok(Some(format!("${}", v)))
+                }
+            }
+            Expression::Operation(_) | Expression::Call(_) => Ok(None),
+        }
+    }
+
+    fn push(&mut self, register: &str) {
+        self.gen.raw(format!("push {}", register));
+        self.stack_depth += 1;
+    }
+
+    fn pop(&mut self, register: &str) {
+        self.gen.raw(format!("pop {}", register));
+        self.stack_depth -= 1;
+    }
+
+    /// Adds the value of the string literal `lit` to the pool, unless an equal
+    /// string is already there, and returns its label.
+    fn string_label(&mut self, lit: &Literal) -> Result<String, CodegenError> {
+        let value = text_value(lit)?;
+        let n = match self.strings.iter().position(|s| *s == value) {
+            Some(n) => n,
+            None => {
+                self.strings.push(value.to_owned());
+                self.strings.len() - 1
+            }
+        };
+        Ok(string_label(n))
+    }
+
+    fn new_label(&mut self) -> String {
+        let label = format!(".L{}_{}", self.label, self.branch_counter);
+        self.branch_counter += 1;
+        label
+    }
+
+    /// Evaluates `left` as `ty` into the accumulator and returns an operand for `right`.
+    ///
+    /// Operations on the right are evaluated first and kept on the stack while the
+    /// left side is evaluated, then popped into `%rcx`.
+    fn gen_operands(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        ty: PrimitiveTy,
+    ) -> Result<String, CodegenError> {
+        if let Some(r_ref) = self.operand(right, ty)? {
+            self.gen_value_as(left, ty)?;
+            return Ok(r_ref);
+        }
+        self.gen_value_as(right, ty)?;
+        self.push("%rax");
+        self.gen_value_as(left, ty)?;
+        self.pop("%rcx");
+        Ok(sized(RCX, operand_size(ty)?).to_owned())
+    }
+
+    /// Evaluates the arguments into registers and calls the function, leaving the
+    /// result in the accumulator.
+    fn gen_call(&mut self, call: &Call) -> Result<(), CodegenError> {
+        let signatures = self.signatures;
+        let signature = signatures
+            .get(call.name)
+            .ok_or_else(|| TypeError::UnknownFunction(call.name.to_owned()))?;
+        let locations = argument_locations(
+            &signature.arguments[..call.arguments.len().min(signature.arguments.len())],
+        );
+        // Every argument is pushed before any is moved into place so evaluating
+        // one can't clobber the registers of the others.
+        for (argument, &ty) in call.arguments.iter().zip(&signature.arguments) {
+            self.gen_value_as(argument, ty)?;
+            self.push("%rax");
+        }
+        let pushed = locations.len();
+        // Offset of the `i`th pushed argument once `extra` more slots are pushed.
+        let offset = |i: usize, extra: usize| 8 * (pushed - 1 - i + extra);
+        for (i, location) in locations.iter().enumerate() {
+            match location {
+                ArgumentLocation::General(register) => self.gen.raw(format!(
+                    "movq {}(%rsp), {}",
+                    offset(i, 0),
+                    sized(*register, 8)
+                )),
+                ArgumentLocation::Float(n) => {
+                    self.gen.raw(format!("movq {}(%rsp), %xmm{}", offset(i, 0), n))
+                }
+                ArgumentLocation::Stack(_) => {}
+            }
+        }
+        // Stack arguments are copied right to left so the first ends up on top.
+        // Padding goes in before them so the stack is 16-byte aligned at the call.
+        let stack_arguments: Vec<usize> = locations
+            .iter()
+            .enumerate()
+            .filter(|(_, location)| matches!(location, ArgumentLocation::Stack(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let mut extra = (self.stack_depth + stack_arguments.len()) % 2;
+        if extra == 1 {
+            self.gen.raw("sub $8, %rsp");
+        }
+        for &i in stack_arguments.iter().rev() {
+            self.gen.raw(format!("pushq {}(%rsp)", offset(i, extra)));
+            extra += 1;
+        }
+        self.gen.raw(format!("call {}", function_label(call.name)));
+        if pushed + extra > 0 {
+            self.gen.raw(format!("add ${}, %rsp", 8 * (pushed + extra)));
+        }
+        self.stack_depth -= pushed;
+        if signature.return_type.is_float() {
+            self.gen.raw("movq %xmm0, %rax");
+        }
+        Ok(())
+    }
+
+    /// Evaluates `expr` into the accumulator, returning its type. Unsuffixed
+    /// literals are evaluated as `i32`.
+    fn gen_value(&mut self, expr: &Expression) -> Result<PrimitiveTy, CodegenError> {
+        let ty = self.get_type(expr)?.defaulted();
+        self.gen_value_as(expr, ty)?;
+        Ok(ty)
+    }
+
+    /// Evaluates `expr` as a value of type `ty` into the accumulator (`%al`,
+    /// `%eax` or `%rax`). The caller has checked that `expr` coerces to `ty`.
+    fn gen_value_as(&mut self, expr: &Expression, ty: PrimitiveTy) -> Result<(), CodegenError> {
+        if let Expression::Call(call) = expr {
+            return self.gen_call(call);
+        }
+        let ty = ty.defaulted();
+        let size = operand_size(ty)?;
+        let (suffix, reg) = (suffix(size), sized(RAX, size));
+        let op = match expr {
+            Expression::Call(_) => unreachable!("calls are generated above"),
+            Expression::Variable(var) => return self.load(self.local(var)?, RAX),
+            Expression::Literal(lit) if lit.ty == PrimitiveTy::Str => {
+                let label = self.string_label(lit)?;
+                self.gen.raw(format!("leaq {}(%rip), %rax", label));
+                return Ok(());
+            }
+            Expression::Literal(lit) => {
+                let v = literal_value(lit, ty)?;
+                self.gen_literal(v, ty)?;
+                return Ok(());
+            }
+            Expression::Operation(op) => op,
+        };
+        match &**op {
+            // Fold negative literals so the most negative value of a type is in range.
+            Operation::Unary {
+                target: Expression::Literal(lit),
+                ty: UnaryOperationTy::Negate,
+            } => {
+                let v = negated_literal_value(lit, ty)?;
+                self.gen_literal(v, ty)?;
+            }
+            Operation::Unary { target, ty: op_ty } => {
+                // An unsuffixed operand takes on the expected type, which may be unsigned.
+                if *op_ty == UnaryOperationTy::Negate
+                    && matches!(ty, PrimitiveTy::Integer(int) if !int.is_signed())
+                {
+                    return Err(TypeError::InvalidOperand {
+                        operator: op_ty.symbol(),
+                        ty,
+                    }
+                    .into());
+                }
+                self.gen_value_as(target, ty)?;
+                match (op_ty, ty) {
+                    // Flip the sign bit.
+                    (UnaryOperationTy::Negate, PrimitiveTy::Float(FloatTy::F32)) => {
+                        self.gen.raw("xorl $0x80000000, %eax")
+                    }
+                    (UnaryOperationTy::Negate, PrimitiveTy::Float(FloatTy::F64)) => {
+                        self.gen.raw("btcq $63, %rax")
+                    }
+                    (UnaryOperationTy::Negate, _) => self.gen.raw(format!("neg{} {}", suffix, reg)),
+                    (UnaryOperationTy::Not, PrimitiveTy::Boolean) => self.gen.raw("xorb $1, %al"),
+                    (UnaryOperationTy::Not, _) => self.gen.raw(format!("not{} {}", suffix, reg)),
+                }
+                self.extend(ty);
+            }
+            Operation::Binary {
+                left,
+                ty: op_ty @ (BinaryOperationTy::LogicalAnd | BinaryOperationTy::LogicalOr),
+                right,
+            } => {
+                // Short-circuit: skip the right side once the left decides the result.
+                let end = self.new_label();
+                self.gen_value_as(left, ty)?;
+                self.gen.raw("testb %al, %al");
+                match op_ty {
+                    BinaryOperationTy::LogicalAnd => self.gen.raw(format!("je {}", end)),
+                    _ => self.gen.raw(format!("jne {}", end)),
+                }
+                self.gen_value_as(right, ty)?;
+                self.gen.label(end);
+            }
+            Operation::Binary { left, ty: op_ty, right } => {
+                // Comparisons produce a bool from operands of their own type,
+                // everything else computes in the type of its result.
+                let operand_ty = if op_ty.is_comparison() {
+                    operand_type(self.get_type(left)?, self.get_type(right)?)?.defaulted()
+                } else {
+                    ty
+                };
+                let r_ref = self.gen_operands(left, right, operand_ty)?;
+                self.gen_binary_operation(*op_ty, operand_ty, &r_ref)?;
+            }
+            Operation::CompoundAssignment { .. } => {
+                unreachable!("assignments are lowered by gen_expr")
+            }
+            Operation::Cast { target, .. } => {
+                // An unsuffixed literal is range checked as the target type, or as
+                // the `u8` it has to be to become a `char`. An unsuffixed float is
+                // computed in the target type rather than its default `f64`.
+                let from = match (self.get_type(target)?, ty) {
+                    (PrimitiveTy::Number, PrimitiveTy::Char) => PrimitiveTy::Integer(IntegerTy::U8),
+                    (PrimitiveTy::Number, _) => ty,
+                    (PrimitiveTy::Decimal, _) => ty,
+                    (from, _) => from,
+                };
+                self.gen_value_as(target, from)?;
+                if from == PrimitiveTy::Boolean {
+                    self.gen.raw("movzbl %al, %eax");
+                }
+                // Widening to 64 bits extends by the signedness of the source,
+                // narrowing just drops the upper bits.
+                if size == 8 && operand_size(from)? < 8 {
+                    self.gen.raw(if is_signed(from) {
+                        "movslq %eax, %rax"
+                    } else {
+                        "movl %eax, %eax"
+                    });
+                }
+                self.extend(ty);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the value `v` of type `ty` into the accumulator.
+    fn gen_literal(&mut self, v: i64, ty: PrimitiveTy) -> Result<(), CodegenError> {
+        let size = operand_size(ty)?;
+        let instruction = if i32::try_from(v).is_err() && size == 8 {
+            "movabsq".to_owned()
+        } else {
+            format!("mov{}", suffix(size))
+        };
+        self.gen
+            .raw(format!("{} ${}, {}", instruction, v, sized(RAX, size)));
+        Ok(())
+    }
+
+    /// Applies `op_ty` to the accumulator and `r_ref`, both holding values of `ty`,
+    /// leaving the result in the accumulator.
+    fn gen_binary_operation(
+        &mut self,
+        op_ty: BinaryOperationTy,
+        ty: PrimitiveTy,
+        r_ref: &str,
+    ) -> Result<(), CodegenError> {
+        if let PrimitiveTy::Float(float) = ty {
+            return self.gen_float_operation(op_ty, float, r_ref);
+        }
+        let size = operand_size(ty)?;
+        let (suffix, reg, scratch) = (suffix(size), sized(RAX, size), sized(RCX, size));
+        let signed = is_signed(ty);
+        let instruction = match op_ty {
+            BinaryOperationTy::Addition => "add",
+            BinaryOperationTy::Subtraction => "sub",
+            BinaryOperationTy::Multiplication => "imul",
+            BinaryOperationTy::BitAnd => "and",
+            BinaryOperationTy::BitOr => "or",
+            BinaryOperationTy::BitXor => "xor",
+            BinaryOperationTy::Division | BinaryOperationTy::Remainder => {
+                if r_ref != scratch {
+                    self.gen.raw(format!("mov{} {}, {}", suffix, r_ref, scratch));
+                }
+                if signed {
+                    self.gen.raw(if size == 8 { "cqto" } else { "cltd" });
+                    self.gen.raw(format!("idiv{} {}", suffix, scratch));
+                } else {
+                    self.gen.raw("xorl %edx, %edx");
+                    self.gen.raw(format!("div{} {}", suffix, scratch));
+                }
+                if op_ty == BinaryOperationTy::Remainder {
+                    self.gen
+                        .raw(format!("mov{} {}, {}", suffix, sized(RDX, size), reg));
+                }
+                self.extend(ty);
+                return Ok(());
+            }
+            BinaryOperationTy::ShiftLeft | BinaryOperationTy::ShiftRight => {
+                if r_ref != scratch {
+                    self.gen.raw(format!("mov{} {}, {}", suffix, r_ref, scratch));
+                }
+                let instruction = match op_ty {
+                    BinaryOperationTy::ShiftLeft => "shl",
+                    _ if signed => "sar",
+                    _ => "shr",
+                };
+                self.gen
+                    .raw(format!("{}{} %cl, {}", instruction, suffix, reg));
+                self.extend(ty);
+                return Ok(());
+            }
+            cmp if cmp.is_comparison() => {
+                self.gen.raw(format!("cmp{} {}, {}", suffix, r_ref, reg));
+                self.gen
+                    .raw(format!("set{} %al", condition_code(cmp, signed)));
+                return Ok(());
+            }
+            _ => unreachable!("assignments are lowered by gen_expr"),
+        };
+        self.gen
+            .raw(format!("{}{} {}, {}", instruction, suffix, r_ref, reg));
+        self.extend(ty);
+        Ok(())
+    }
+
+    /// Applies `op_ty` to the accumulator and `r_ref` like [`Self::gen_binary_operation`],
+    /// going through `%xmm0` and `%xmm1` for the SSE2 instructions.
+    fn gen_float_operation(
+        &mut self,
+        op_ty: BinaryOperationTy,
+        float: FloatTy,
+        r_ref: &str,
+    ) -> Result<(), CodegenError> {
+        let (size, sse) = (float.size(), float_suffix(float));
+        let (movd, reg, scratch) = (
+            if size == 8 { "movq" } else { "movd" },
+            sized(RAX, size),
+            sized(RCX, size),
+        );
+        if r_ref.starts_with('%') {
+            self.gen.raw(format!("{} {}, %xmm1", movd, r_ref));
+        } else if r_ref.starts_with('$') {
+            self.gen.raw(format!("mov{} {}, {}", suffix(size), r_ref, scratch));
+            self.gen.raw(format!("{} {}, %xmm1", movd, scratch));
+        } else {
+            self.gen.raw(format!("mov{} {}, %xmm1", sse, r_ref));
+        }
+        self.gen.raw(format!("{} {}, %xmm0", movd, reg));
+        let instruction = match op_ty {
+            BinaryOperationTy::Addition => "add",
+            BinaryOperationTy::Subtraction => "sub",
+            BinaryOperationTy::Multiplication => "mul",
+            BinaryOperationTy::Division => "div",
+            cmp if cmp.is_comparison() => {
+                // `ucomis` sets the flags like an unsigned compare, and sets the
+                // parity flag if either side is NaN, which must compare unequal.
+                // `<` and `<=` swap the operands so NaN makes `a` and `ae` false.
+                let (left, right, code) = match cmp {
+                    BinaryOperationTy::Less => ("%xmm1", "%xmm0", "a"),
+                    BinaryOperationTy::LessEqual => ("%xmm1", "%xmm0", "ae"),
+                    BinaryOperationTy::Greater => ("%xmm0", "%xmm1", "a"),
+                    BinaryOperationTy::GreaterEqual => ("%xmm0", "%xmm1", "ae"),
+                    BinaryOperationTy::Equal => ("%xmm0", "%xmm1", "e"),
+                    _ => ("%xmm0", "%xmm1", "ne"),
+                };
+                self.gen
+                    .raw(format!("ucomi{} {}, {}", sse, right, left));
+                self.gen.raw(format!("set{} %al", code));
+                match cmp {
+                    BinaryOperationTy::Equal => {
+                        self.gen.raw("setnp %cl");
+                        self.gen.raw("andb %cl, %al");
+                    }
+                    BinaryOperationTy::NotEqual => {
+                        self.gen.raw("setp %cl");
+                        self.gen.raw("orb %cl, %al");
+                    }
+                    _ => {}
+                }
+                return Ok(());
+            }
+            _ => unreachable!("{:?} is not a float operation", op_ty),
+        };
+        self.gen
+            .raw(format!("{}{} %xmm1, %xmm0", instruction, sse));
+        self.gen.raw(format!("{} %xmm0, {}", movd, reg));
+        Ok(())
+    }
+
+    /// Applies `ty` to `target` in place, with a single read-modify-write
+    /// instruction where x86 has one.
+    fn gen_compound_assignment(
+        &mut self,
+        target: LocalVar,
+        ty: BinaryOperationTy,
+        value: &Expression,
+    ) -> Result<(), CodegenError> {
+        let size = size_of(target.ty);
+        let target_ref = target.operand();
+        match ty {
+            BinaryOperationTy::Addition
+            | BinaryOperationTy::Subtraction
+            | BinaryOperationTy::BitAnd
+            | BinaryOperationTy::BitOr
+            | BinaryOperationTy::BitXor
+            | BinaryOperationTy::ShiftLeft
+            | BinaryOperationTy::ShiftRight
+                if !target.ty.is_float() =>
+            {
+                // Literals can be used as immediates, anything else is evaluated first.
+                let value_ref = match self.operand(value, target.ty)? {
+                    Some(value_ref) if matches!(value, Expression::Literal(_)) => value_ref,
+                    _ => {
+                        self.gen_value_as(value, target.ty)?;
+                        sized(RAX, size).to_owned()
+                    }
+                };
+                let instruction = match ty {
+                    BinaryOperationTy::Addition => "add",
+                    BinaryOperationTy::Subtraction => "sub",
+                    BinaryOperationTy::BitAnd => "and",
+                    BinaryOperationTy::BitOr => "or",
+                    BinaryOperationTy::BitXor => "xor",
+                    BinaryOperationTy::ShiftLeft => "shl",
+                    _ if is_signed(target.ty) => "sar",
+                    _ => "shr",
+                };
+                let value_ref = match ty {
+                    // Shift counts in a register have to be in `%cl`.
+                    BinaryOperationTy::ShiftLeft | BinaryOperationTy::ShiftRight
+                        if !value_ref.starts_with('$') =>
+                    {
+                        self.gen.raw("movl %eax, %ecx");
+                        "%cl".to_owned()
+                    }
+                    _ => value_ref,
+                };
+                self.gen.raw(format!(
+                    "{}{} {}, {}",
+                    instruction,
+                    suffix(size),
+                    value_ref,
+                    target_ref
+                ));
+            }
+            // `imul`, `idiv` and SSE instructions can't write to memory.
+            _ => {
+                let operand_size = operand_size(target.ty)?;
+                self.gen_value_as(value, target.ty)?;
+                self.gen.raw(format!(
+                    "mov{} {}, {}",
+                    suffix(operand_size),
+                    sized(RAX, operand_size),
+                    sized(RCX, operand_size)
+                ));
+                self.load(target, RAX)?;
+                self.gen_binary_operation(ty, target.ty, sized(RCX, operand_size))?;
+                self.store(target);
+            }
+        }
+        Ok(())
+    }
+
+    /// Jumps to `label` if `condition` is false, falling through otherwise.
+    fn gen_jump_unless(&mut self, condition: &Expression, label: &str) -> Result<(), CodegenError> {
+        expect_type(PrimitiveTy::Boolean, self.get_type(condition)?)?;
+        let comparison = match condition {
+            Expression::Operation(op) => match &**op {
+                Operation::Binary { left, ty, right } if ty.is_comparison() => {
+                    let operand_ty =
+                        operand_type(self.get_type(left)?, self.get_type(right)?)?.defaulted();
+                    if operand_ty.is_integer() || operand_ty == PrimitiveTy::Char {
+                        Some((left, *ty, right, operand_ty))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        match comparison {
+            // Compare directly and jump on the inverted condition.
+            Some((left, ty, right, operand_ty)) => {
+                let r_ref = self.gen_operands(left, right, operand_ty)?;
+                let size = operand_size(operand_ty)?;
+                self.gen
+                    .raw(format!("cmp{} {}, {}", suffix(size), r_ref, sized(RAX, size)));
+                self.gen.raw(format!(
+                    "j{} {}",
+                    condition_code(inverse_comparison(ty), is_signed(operand_ty)),
+                    label
+                ));
+            }
+            None => {
+                self.gen_value(condition)?;
+                self.gen.raw("testb %al, %al");
+                self.gen.raw(format!("je {}", label));
+            }
+        }
+        Ok(())
+    }
+
+    fn gen_if(
+        &mut self,
+        condition: &Expression,
+        body: &'a [Statement<'a>],
+        else_body: Option<&'a [Statement<'a>]>,
+    ) -> Result<(), CodegenError> {
+        let else_label = self.new_label();
+        self.gen_jump_unless(condition, &else_label)?;
+        self.gen_block(body)?;
+        match else_body {
+            Some(else_body) => {
+                let end_label = self.new_label();
+                self.gen.raw(format!("jmp {}", end_label));
+                self.gen.label(else_label);
+                self.gen_block(else_body)?;
+                self.gen.label(end_label);
+            }
+            None => self.gen.label(else_label),
+        }
+        Ok(())
+    }
+
+    /// Generates a `while` loop, or an infinite `loop` when there is no condition.
+    fn gen_loop(
+        &mut self,
+        label: Option<&str>,
+        condition: Option<&Expression>,
+        body: &'a [Statement<'a>],
+    ) -> Result<(), CodegenError> {
+        let start_label = self.new_label();
+        let end_label = self.new_label();
+        self.gen.label(&start_label);
+        if let Some(condition) = condition {
+            self.gen_jump_unless(condition, &end_label)?;
+        }
+        self.loops.push(LoopLabels {
+            name: label.map(str::to_owned),
+            continue_label: start_label.clone(),
+            break_label: end_label.clone(),
+        });
+        self.gen_block(body)?;
+        self.loops.pop();
+        self.gen.raw(format!("jmp {}", start_label));
+        self.gen.label(end_label);
+        Ok(())
+    }
+
+    /// Generates a counted loop over `start..end` or `start..=end`.
+    ///
+    /// Both bounds are evaluated once up front. The count lives in a hidden slot
+    /// that is copied into `variable` on every iteration, so assigning to the
+    /// variable in the body doesn't change how often the loop runs.
+    fn gen_for(
+        &mut self,
+        label: Option<&str>,
+        variable: &'a str,
+        start: &Expression,
+        end: &Expression,
+        inclusive: bool,
+        body: &'a [Statement<'a>],
+    ) -> Result<(), CodegenError> {
+        let ty = operand_type(self.get_type(start)?, self.get_type(end)?)?.defaulted();
+        if !ty.is_integer() {
+            return Err(TypeError::InvalidOperand { operator: "..", ty }.into());
+        }
+        let size = operand_size(ty)?;
+        let counter = self.allocate_slot(ty)?;
+        let limit = self.allocate_slot(ty)?;
+        self.gen_expr(start, Some(counter))?;
+        self.gen_expr(end, Some(limit))?;
+
+        let check_label = self.new_label();
+        let step_label = self.new_label();
+        let end_label = self.new_label();
+        let compare = format!("cmp{} {}, {}", suffix(size), sized(RCX, size), sized(RAX, size));
+        self.gen.label(&check_label);
+        self.load(counter, RAX)?;
+        self.load(limit, RCX)?;
+        self.gen.raw(&compare);
+        let exit = if inclusive {
+            BinaryOperationTy::Greater
+        } else {
+            BinaryOperationTy::GreaterEqual
+        };
+        self.gen.raw(format!(
+            "j{} {}",
+            condition_code(exit, is_signed(ty)),
+            end_label
+        ));
+
+        self.scopes.push(HashMap::new());
+        let local = self.allocate_slot(ty)?;
+        self.declare(variable, local);
+        self.store(local);
+        self.loops.push(LoopLabels {
+            name: label.map(str::to_owned),
+            continue_label: step_label.clone(),
+            break_label: end_label.clone(),
+        });
+        self.gen_block(body)?;
+        self.loops.pop();
+        self.scopes.pop();
+
+        self.gen.label(step_label);
+        if inclusive {
+            // Stop before incrementing so an `end` of the type's maximum can't overflow.
+            self.load(counter, RAX)?;
+            self.load(limit, RCX)?;
+            self.gen.raw(&compare);
+            self.gen.raw(format!("je {}", end_label));
+        }
+        self.gen.raw(format!(
+            "add{} $1, {}",
+            suffix(size_of(ty)),
+            counter.operand()
+        ));
+        self.gen.raw(format!("jmp {}", check_label));
+        self.gen.label(end_label);
+        Ok(())
+    }
+
+    /// Finds the loop a `break` or `continue` with an optional label refers to.
+    fn loop_labels(
+        &self,
+        keyword: &'static str,
+        label: Option<&str>,
+    ) -> Result<&LoopLabels, CodegenError> {
+        match label {
+            None => self.loops.last().ok_or(CodegenError::OutsideLoop(keyword)),
+            Some(label) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|l| l.name.as_deref() == Some(label))
+                .ok_or_else(|| CodegenError::UnknownLabel(label.to_owned())),
+        }
+    }
+}
+
+/// Whether control can't reach the end of `body` without hitting a `return`.
+fn always_returns(body: &[Statement]) -> bool {
+    match body.last() {
+        Some(Statement::Return(_)) => true,
+        Some(Statement::ControlFlow(ControlFlow::If {
+            body,
+            else_body: Some(else_body),
+            ..
+        })) => always_returns(body) && always_returns(else_body),
+        // A `loop` is only left through a `break`.
+        Some(Statement::ControlFlow(ControlFlow::Loop { label, body })) => {
+            !breaks_out(body, *label, true)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `body` contains a `break` leaving the loop named `label`, where
+/// `innermost` says if an unlabeled `break` would still refer to it.
+fn breaks_out(body: &[Statement], label: Option<&str>, innermost: bool) -> bool {
+    body.iter().any(|stmt| match stmt {
+        Statement::ControlFlow(ControlFlow::Break(None)) => innermost,
+        Statement::ControlFlow(ControlFlow::Break(Some(target))) => Some(*target) == label,
+        Statement::ControlFlow(ControlFlow::If {
+            body, else_body, ..
+        }) => {
+            breaks_out(body, label, innermost)
+                || else_body
+                    .as_ref()
+                    .is_some_and(|else_body| breaks_out(else_body, label, innermost))
+        }
+        Statement::ControlFlow(
+            ControlFlow::While { body, .. }
+            | ControlFlow::Loop { body, .. }
+            | ControlFlow::For { body, .. },
+        ) => breaks_out(body, label, false),
+        _ => false,
+    })
+}
+
+/// The value of `lit` used as a `ty`, checked against the range of `ty`.
+fn literal_value(lit: &Literal, ty: PrimitiveTy) -> Result<i64, CodegenError> {
+    match ty.defaulted() {
+        PrimitiveTy::Integer(int) => check_range(lit.value, int, integer_value(lit)?),
+        PrimitiveTy::Float(float) => float_bits(lit.value, float, false),
+        PrimitiveTy::Boolean => Ok(lit
+            .value
+            .parse::<bool>()
+            .map_err(|_| CodegenError::InvalidLiteral(lit.value.to_owned()))?
+            as i64),
+        PrimitiveTy::Char => Ok(text_value(lit)?.chars().next().map_or(0, |c| c as i64)),
+        PrimitiveTy::Str => unreachable!("string literals are loaded by address"),
+        PrimitiveTy::Number | PrimitiveTy::Decimal | PrimitiveTy::Void => {
+            Err(CodegenError::Unsupported("values of type Void"))
+        }
+    }
+}
+
+/// The value of `-lit` used as a `ty`, which allows the most negative value of
+/// a signed type even though its magnitude alone is out of range.
+fn negated_literal_value(lit: &Literal, ty: PrimitiveTy) -> Result<i64, CodegenError> {
+    match ty.defaulted() {
+        PrimitiveTy::Integer(int) => {
+            check_range(&format!("-{}", lit.value), int, -integer_value(lit)?)
+        }
+        PrimitiveTy::Float(float) => float_bits(lit.value, float, true),
+        _ => Err(TypeError::InvalidOperand {
+            operator: UnaryOperationTy::Negate.symbol(),
+            ty,
+        }
+        .into()),
+    }
+}
+
+/// The value of an integer literal, not yet checked against the range of a type.
+fn integer_value(lit: &Literal) -> Result<i128, CodegenError> {
+    match lit.parsed {
+        Some(ParsedLiteral::Integer(value)) => Ok(value.into()),
+        _ => Err(CodegenError::InvalidLiteral(lit.value.to_owned())),
+    }
+}
+
+/// The unescaped contents of a string or char literal.
+fn text_value<'l>(lit: &'l Literal) -> Result<&'l str, CodegenError> {
+    match &lit.parsed {
+        Some(ParsedLiteral::Text(text)) => Ok(text),
+        _ => Err(CodegenError::InvalidLiteral(lit.value.to_owned())),
+    }
+}
+
+/// The bit pattern of the float literal `text`, negated if `negate` is set. An
+/// `f32` is returned sign-extended so it can be used as a 32-bit immediate.
+fn float_bits(text: &str, float: FloatTy, negate: bool) -> Result<i64, CodegenError> {
+    // Skip a type suffix such as the `f32` in `1.5f32`.
+    let digits = text
+        .strip_suffix("f32")
+        .or_else(|| text.strip_suffix("f64"))
+        .unwrap_or(text)
+        .replace('_', "");
+    let v = digits
+        .parse::<f64>()
+        .map_err(|_| CodegenError::InvalidLiteral(text.to_owned()))?;
+    let v = if negate { -v } else { v };
+    let (bits, infinite) = match float {
+        FloatTy::F32 => ((v as f32).to_bits() as i32 as i64, (v as f32).is_infinite()),
+        FloatTy::F64 => (v.to_bits() as i64, v.is_infinite()),
+    };
+    if infinite {
+        return Err(CodegenError::LiteralOutOfRange {
+            value: if negate { format!("-{}", text) } else { text.to_owned() },
+            ty: PrimitiveTy::Float(float),
+        });
+    }
+    Ok(bits)
+}
+
+/// Checks that the value `v` of the literal `text` fits in `int`, returning its
+/// bit pattern as an `i64`.
+fn check_range(text: &str, int: IntegerTy, v: i128) -> Result<i64, CodegenError> {
+    let (min, max) = int.range();
+    if v < min || v > max {
+        return Err(CodegenError::LiteralOutOfRange {
+            value: text.to_owned(),
+            ty: PrimitiveTy::Integer(int),
+        });
+    }
+    Ok(v as i64)
+}
+
+/// Condition code suffix for `set`/`j` instructions after a `cmp`.
+fn condition_code(ty: BinaryOperationTy, signed: bool) -> &'static str {
+    match (ty, signed) {
+        (BinaryOperationTy::Equal, _) => "e",
+        (BinaryOperationTy::NotEqual, _) => "ne",
+        (BinaryOperationTy::Less, true) => "l",
+        (BinaryOperationTy::LessEqual, true) => "le",
+        (BinaryOperationTy::Greater, true) => "g",
+        (BinaryOperationTy::GreaterEqual, true) => "ge",
+        (BinaryOperationTy::Less, false) => "b",
+        (BinaryOperationTy::LessEqual, false) => "be",
+        (BinaryOperationTy::Greater, false) => "a",
+        (BinaryOperationTy::GreaterEqual, false) => "ae",
+        _ => unreachable!("{:?} is not a comparison", ty),
+    }
+}
+
+fn inverse_comparison(ty: BinaryOperationTy) -> BinaryOperationTy {
+    match ty {
+        BinaryOperationTy::Equal => BinaryOperationTy::NotEqual,
+        BinaryOperationTy::NotEqual => BinaryOperationTy::Equal,
+        BinaryOperationTy::Less => BinaryOperationTy::GreaterEqual,
+        BinaryOperationTy::LessEqual => BinaryOperationTy::Greater,
+        BinaryOperationTy::Greater => BinaryOperationTy::LessEqual,
+        BinaryOperationTy::GreaterEqual => BinaryOperationTy::Less,
+        _ => unreachable!("{:?} is not a comparison", ty),
+    }
+}
@@ -8,16 +8,55 @@ lazy_static! {
         let mut map = HashMap::new();
         map.insert("->", SymbolTy::ThinArrow);
         map.insert("+=", SymbolTy::AddAssign);
+        map.insert("-=", SymbolTy::SubAssign);
+        map.insert("*=", SymbolTy::MulAssign);
+        map.insert("/=", SymbolTy::DivAssign);
+        map.insert("%=", SymbolTy::RemAssign);
+        map.insert("&=", SymbolTy::BitAndAssign);
+        map.insert("|=", SymbolTy::BitOrAssign);
+        map.insert("^=", SymbolTy::BitXorAssign);
         map.insert("=", SymbolTy::Assign);
         map.insert("+", SymbolTy::Add);
+        map.insert("-", SymbolTy::Sub);
+        map.insert("*", SymbolTy::Mul);
+        map.insert("/", SymbolTy::Div);
+        map.insert("%", SymbolTy::Rem);
+        map.insert("==", SymbolTy::Equal);
+        map.insert("!=", SymbolTy::NotEqual);
+        map.insert("<", SymbolTy::Less);
+        map.insert("<=", SymbolTy::LessEqual);
         map.insert(">", SymbolTy::Greater);
+        map.insert(">=", SymbolTy::GreaterEqual);
+        map.insert("&&", SymbolTy::LogicalAnd);
+        map.insert("||", SymbolTy::LogicalOr);
+        map.insert("!", SymbolTy::Not);
+        map.insert("&", SymbolTy::BitAnd);
+        map.insert("|", SymbolTy::BitOr);
+        map.insert("^", SymbolTy::BitXor);
+        map.insert("<<", SymbolTy::ShiftLeft);
+        map.insert(">>", SymbolTy::ShiftRight);
+        map.insert("<<=", SymbolTy::ShiftLeftAssign);
+        map.insert(">>=", SymbolTy::ShiftRightAssign);
         map.insert("(", SymbolTy::OpParen);
         map.insert(")", SymbolTy::ClParen);
         map.insert("{", SymbolTy::OpBrace);
         map.insert("}", SymbolTy::ClBrace);
         map.insert(";", SymbolTy::EndStmt);
+        map.insert(":", SymbolTy::Colon);
+        map.insert("::", SymbolTy::PathSep);
+        map.insert(",", SymbolTy::Comma);
+        map.insert("..", SymbolTy::Range);
+        map.insert("..=", SymbolTy::RangeInclusive);
+        map.insert("=>", SymbolTy::FatArrow);
         map
     };
+    /// [`SYMBOLS`] ordered longest first, so the first entry that prefixes the input
+    /// is the longest match regardless of hash order.
+    pub static ref SYMBOLS_BY_LENGTH: Vec<(&'static str, SymbolTy)> = {
+        let mut symbols: Vec<_> = SYMBOLS.iter().map(|(text, ty)| (*text, *ty)).collect();
+        symbols.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(b.0)));
+        symbols
+    };
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -25,13 +64,45 @@ pub enum SymbolTy {
     Assign,
     Add,
     AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    RemAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
     Greater,
+    GreaterEqual,
+    LogicalAnd,
+    LogicalOr,
+    Not,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    ShiftLeftAssign,
+    ShiftRightAssign,
     OpParen,
     ClParen,
     OpBrace,
     ClBrace,
     EndStmt,
+    Colon,
+    PathSep,
+    Comma,
     ThinArrow,
+    Range,
+    RangeInclusive,
+    FatArrow,
 }
 
 lazy_static! {
@@ -40,7 +111,15 @@ lazy_static! {
         map.insert("fn", KeywordTy::Function);
         map.insert("let", KeywordTy::Let);
         map.insert("if", KeywordTy::If);
+        map.insert("else", KeywordTy::Else);
+        map.insert("while", KeywordTy::While);
+        map.insert("loop", KeywordTy::Loop);
+        map.insert("break", KeywordTy::Break);
+        map.insert("continue", KeywordTy::Continue);
+        map.insert("for", KeywordTy::For);
+        map.insert("in", KeywordTy::In);
         map.insert("return", KeywordTy::Return);
+        map.insert("as", KeywordTy::As);
         map
     };
 }
@@ -50,7 +129,15 @@ pub enum KeywordTy {
     Function, // fn
     Let,      // let
     If,       // if
+    Else,     // else
+    While,    // while
+    Loop,     // loop
+    Break,    // break
+    Continue, // continue
+    For,      // for
+    In,       // in
     Return,
+    As,       // as
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -59,6 +146,10 @@ pub enum TokenTy {
     Symbol(SymbolTy),
     Keyword(KeywordTy),
     Identifier,
+    /// A loop label such as `'outer`, including the leading `'`.
+    Label,
+    /// A `##` comment documenting the item after it, up to the end of the line.
+    DocComment,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -66,3 +157,22 @@ pub struct Token {
     pub range: Range<usize>,
     pub ty: TokenTy,
 }
+
+impl std::fmt::Display for TokenTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenTy::Literal(ty) => write!(f, "{:?} literal", ty),
+            TokenTy::Symbol(symbol) => {
+                let text = SYMBOLS.iter().find(|s| s.1 == symbol).unwrap().0;
+                write!(f, "`{}`", text)
+            }
+            TokenTy::Keyword(keyword) => {
+                let text = KEYWORDS.iter().find(|k| k.1 == keyword).unwrap().0;
+                write!(f, "`{}`", text)
+            }
+            TokenTy::Identifier => write!(f, "identifier"),
+            TokenTy::Label => write!(f, "label"),
+            TokenTy::DocComment => write!(f, "doc comment"),
+        }
+    }
+}
@@ -1,17 +1,131 @@
-use itertools::Itertools;
 use std::ops::Range;
 
-use crate::{PrimitiveTy, TextSection};
+use crate::{IntegerTy, PrimitiveTy, TextSection};
 
-use super::token::{KeywordTy, SymbolTy, Token, TokenTy, KEYWORDS, SYMBOLS};
+use super::token::{SymbolTy, Token, TokenTy, KEYWORDS, SYMBOLS_BY_LENGTH};
 
 enum TokenResult {
     Token(Token),
     Skip,
-    InvalidCharacter(char),
+    InvalidCharacter,
+    Error(LexError),
     End,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LexErrorTy {
+    InvalidCharacters,
+    /// A `\` in a string or character literal that doesn't start a supported
+    /// escape sequence.
+    InvalidEscape,
+    UnterminatedString,
+    UnterminatedChar,
+    /// A character literal that doesn't hold exactly one character.
+    InvalidChar,
+    /// A digit that isn't valid in the base of an integer literal, like the `2`
+    /// in `0b102`.
+    InvalidDigit,
+    /// A base prefix such as `0x` without any digits after it.
+    MissingDigits,
+    /// An integer literal too large for any integer type.
+    IntegerOverflow,
+    UnterminatedComment,
+}
+
+impl std::fmt::Display for LexErrorTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LexErrorTy::InvalidCharacters => write!(f, "invalid characters"),
+            LexErrorTy::InvalidEscape => write!(f, "invalid escape sequence"),
+            LexErrorTy::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorTy::UnterminatedChar => write!(f, "unterminated character literal"),
+            LexErrorTy::InvalidChar => {
+                write!(f, "character literal must contain exactly one character")
+            }
+            LexErrorTy::InvalidDigit => write!(f, "invalid digit for the base of the literal"),
+            LexErrorTy::MissingDigits => write!(f, "missing digits after the base prefix"),
+            LexErrorTy::IntegerOverflow => write!(f, "integer literal is too large"),
+            LexErrorTy::UnterminatedComment => write!(f, "unterminated block comment"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LexError {
+    pub section: TextSection,
+    pub ty: LexErrorTy,
+}
+
+/// The value of an integer literal such as `0xFF`, `1_000` or `10u8`, ignoring
+/// its base prefix, digit separators and type suffix.
+pub fn parse_integer(text: &str) -> Result<u64, LexErrorTy> {
+    let (radix, digits) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        _ => (10, text),
+    };
+    // Type suffixes start with `i` or `u`, which aren't digits in any base.
+    let digits = match digits.find(['i', 'u']) {
+        Some(start) if IntegerTy::from_name(&digits[start..]).is_some() => &digits[..start],
+        _ => digits,
+    };
+    let mut value = None;
+    for c in digits.chars().filter(|&c| c != '_') {
+        let digit = c.to_digit(radix).ok_or(LexErrorTy::InvalidDigit)?;
+        value = Some(
+            value
+                .unwrap_or(0u64)
+                .checked_mul(radix as u64)
+                .and_then(|v| v.checked_add(digit as u64))
+                .ok_or(LexErrorTy::IntegerOverflow)?,
+        );
+    }
+    value.ok_or(LexErrorTy::MissingDigits)
+}
+
+/// Decodes the escape sequences in the body of a string or character literal, or
+/// returns the byte range of the first invalid one within `body`.
+pub fn unescape(body: &str) -> Result<String, (LexErrorTy, Range<usize>)> {
+    let mut value = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(backslash) = rest.find('\\') {
+        value.push_str(&rest[..backslash]);
+        let escape = &rest[backslash..];
+        let (c, length) = match escape[1..].chars().next() {
+            Some('n') => (Some('\n'), 2),
+            Some('t') => (Some('\t'), 2),
+            Some('\\') => (Some('\\'), 2),
+            Some('"') => (Some('"'), 2),
+            Some('\'') => (Some('\''), 2),
+            // `\u{1F600}` with one to six hex digits naming a valid `char`.
+            Some('u') => match escape[2..]
+                .strip_prefix('{')
+                .and_then(|hex| Some(&hex[..hex.find('}')?]))
+            {
+                Some(hex) => {
+                    let c = Some(hex)
+                        .filter(|hex| (1..=6).contains(&hex.len()))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32);
+                    (c, hex.len() + 4)
+                }
+                None => (None, 2),
+            },
+            Some(c) => (None, 1 + c.len_utf8()),
+            None => (None, 1),
+        };
+        let start = body.len() - rest.len() + backslash;
+        match c {
+            Some(c) => value.push(c),
+            None => return Err((LexErrorTy::InvalidEscape, start..start + length)),
+        }
+        rest = &escape[length..];
+    }
+    value.push_str(rest);
+    Ok(value)
+}
+
 #[derive(Debug, Clone)]
 pub struct Tokenizer<'a> {
     pub remaining: &'a str,
@@ -21,7 +135,7 @@ pub struct Tokenizer<'a> {
 }
 
 impl<'a> Tokenizer<'a> {
-    pub fn new(s: &str) -> Tokenizer {
+    pub fn new(s: &str) -> Tokenizer<'_> {
         Tokenizer {
             remaining: s,
             line: 0,
@@ -30,59 +144,190 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
-    fn read_number(&mut self) -> Range<usize> {
-        let length = self
-            .remaining
-            .chars()
-            .peeking_take_while(|c| c.is_digit(10))
-            .count();
+    /// Reads an integer or floating-point literal along with a type suffix such as
+    /// the `u8` in `10u8`. The whole literal is skipped even if its digits are invalid.
+    fn read_number(&mut self) -> Result<(PrimitiveTy, Range<usize>), LexError> {
+        let digits_at = |start: usize| {
+            self.remaining[start..]
+                .bytes()
+                .take_while(|&c| c.is_ascii_digit() || c == b'_')
+                .count()
+        };
+        let digit_at = |start: usize| {
+            self.remaining[start..].starts_with(|c: char| c.is_ascii_digit())
+        };
+        let (ty, length) = if ["0x", "0o", "0b"].iter().any(|p| self.remaining.starts_with(p)) {
+            // Take everything up to the end of the word as digits, so `0b102`
+            // reports the `2` rather than lexing as `0b10` and `2`.
+            let length = 2 + self.remaining[2..]
+                .bytes()
+                .take_while(|&c| c.is_ascii_alphanumeric() || c == b'_')
+                .count();
+            let suffix = self.remaining[2..length]
+                .find(['i', 'u'])
+                .and_then(|start| IntegerTy::from_name(&self.remaining[2 + start..length]));
+            (suffix.map_or(PrimitiveTy::Number, PrimitiveTy::Integer), length)
+        } else {
+            let mut length = digits_at(0);
+            let mut float = false;
+            // A fraction needs a digit after the `.`, so ranges like `0..n` still lex.
+            if self.remaining[length..].starts_with('.') && digit_at(length + 1) {
+                float = true;
+                length += 1 + digits_at(length + 1);
+            }
+            if self.remaining[length..].starts_with(['e', 'E']) {
+                let mut exponent = length + 1;
+                if self.remaining[exponent..].starts_with(['+', '-']) {
+                    exponent += 1;
+                }
+                if digit_at(exponent) {
+                    float = true;
+                    length = exponent + digits_at(exponent);
+                }
+            }
+            let suffix_length: usize = self.remaining[length..]
+                .chars()
+                .take_while(|&c| c.is_alphanumeric() || c == '_')
+                .map(char::len_utf8)
+                .sum();
+            let suffix =
+                match PrimitiveTy::from_name(&self.remaining[length..length + suffix_length]) {
+                    Some(ty @ PrimitiveTy::Float(_)) => Some(ty),
+                    Some(ty @ PrimitiveTy::Integer(_)) if !float => Some(ty),
+                    _ => None,
+                };
+            match suffix {
+                Some(ty) => (ty, length + suffix_length),
+                None if float => (PrimitiveTy::Decimal, length),
+                None => (PrimitiveTy::Number, length),
+            }
+        };
+        let error = match ty {
+            PrimitiveTy::Number | PrimitiveTy::Integer(_) => {
+                parse_integer(&self.remaining[..length]).err()
+            }
+            _ => None,
+        };
+        let error = error.map(|ty| LexError {
+            section: self.section(0..length),
+            ty,
+        });
         let range = self.index..self.index + length;
-        self.increment_col(length);
-        self.remaining = &self.remaining[length..];
-        range
+        self.advance(length);
+        match error {
+            Some(error) => Err(error),
+            None => Ok((ty, range)),
+        }
     }
 
-    fn read_symbol(&mut self) -> (SymbolTy, Range<usize>) {
-        let start_index = self.index;
-        let (symbols_str, ty) = SYMBOLS
-            .iter()
-            .find(|symbol| self.remaining.starts_with(symbol.0))
-            .expect("Not a proper symbol");
-        let symbol_length = symbols_str.len();
-        self.increment_char(symbol_length);
-        self.increment_col(symbol_length);
-        (*ty, start_index..self.index)
+    /// Reads a string or character literal delimited by `quote`, including the
+    /// quotes. The whole literal is skipped even if it contains an invalid escape
+    /// sequence.
+    fn read_quoted(&mut self, quote: char) -> Result<Range<usize>, LexError> {
+        // Find the closing quote, stepping over escaped characters. Character
+        // literals can't span lines, so don't look for their end past one.
+        let mut chars = self.remaining.char_indices().skip(1);
+        let end = loop {
+            match chars.next() {
+                Some((i, c)) if c == quote => break Ok(i + 1),
+                Some((_, '\\')) => {
+                    chars.next();
+                }
+                Some((i, '\n')) if quote == '\'' => break Err(i),
+                Some(_) => {}
+                None => break Err(self.remaining.len()),
+            }
+        };
+        let error = match end {
+            Ok(end) => match unescape(&self.remaining[1..end - 1]) {
+                Ok(value) if quote == '\'' && value.chars().count() != 1 => {
+                    Some((LexErrorTy::InvalidChar, 0..end))
+                }
+                Ok(_) => None,
+                Err((ty, range)) => Some((ty, range.start + 1..range.end + 1)),
+            },
+            Err(length) if quote == '\'' => Some((LexErrorTy::UnterminatedChar, 0..length)),
+            Err(length) => Some((LexErrorTy::UnterminatedString, 0..length)),
+        };
+        let error = error.map(|(ty, range)| LexError {
+            section: self.section(range),
+            ty,
+        });
+        let length = match end {
+            Ok(length) | Err(length) => length,
+        };
+        let range = self.index..self.index + length;
+        self.advance(length);
+        match error {
+            Some(error) => Err(error),
+            None => Ok(range),
+        }
     }
 
-    fn read_keyword(&mut self) -> (KeywordTy, Range<usize>) {
-        let start_index = self.index;
-        let (keyword_str, ty) = KEYWORDS
-            .iter()
-            .find(|keyword| self.remaining.starts_with(keyword.0))
-            .expect("Not a proper keyword");
-        let keyword_length = keyword_str.len();
-        self.increment_char(keyword_length);
-        self.increment_col(keyword_length);
-        (*ty, start_index..self.index)
+    /// Skips a `/* */` comment, including any comments nested inside it.
+    fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        let bytes = self.remaining.as_bytes();
+        let mut depth = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i..].starts_with(b"/*") {
+                depth += 1;
+                i += 2;
+            } else if bytes[i..].starts_with(b"*/") {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    self.advance(i);
+                    return Ok(());
+                }
+            } else {
+                i += 1;
+            }
+        }
+        let error = LexError {
+            section: self.section(0..bytes.len()),
+            ty: LexErrorTy::UnterminatedComment,
+        };
+        self.advance(bytes.len());
+        Err(error)
     }
 
-    fn increment_col(&mut self, n: usize) {
-        self.column += n;
-        self.index += n;
+    /// The section covering the byte `range` of the remaining input.
+    fn section(&self, range: Range<usize>) -> TextSection {
+        let relative = TextSection::from_index(self.remaining, range.clone());
+        let column = |line: usize, column: usize| {
+            if line == 0 {
+                self.column + column
+            } else {
+                column
+            }
+        };
+        TextSection {
+            index: self.index + range.start..self.index + range.end,
+            line: self.line + relative.line.start..self.line + relative.line.end,
+            column: column(relative.line.start, relative.column.start)
+                ..column(relative.line.end, relative.column.end),
+        }
     }
 
-    fn increment_line(&mut self) {
-        self.column = 0;
-        self.line += 1;
-        self.index += 1;
+    /// Moves past the next `length` bytes, which may span several lines.
+    fn advance(&mut self, length: usize) {
+        let end = self.section(length..length);
+        self.line = end.line.end;
+        self.column = end.column.end;
+        self.index = end.index.end;
+        self.remaining = &self.remaining[length..];
     }
 
-    fn increment_char(&mut self, n: usize) {
-        let next_index = match self.remaining.char_indices().nth(n) {
-            Some(o) => o.0,
-            None => self.remaining.len(),
-        };
-        self.remaining = &self.remaining[next_index..];
+    /// Reads the longest symbol at the start of the input, so `<<=` doesn't lex as
+    /// `<<` followed by `=`.
+    fn read_symbol(&mut self) -> Option<(SymbolTy, Range<usize>)> {
+        let start_index = self.index;
+        let (symbols_str, ty) = SYMBOLS_BY_LENGTH
+            .iter()
+            .find(|symbol| self.remaining.starts_with(symbol.0))?;
+        self.advance(symbols_str.len());
+        Some((*ty, start_index..self.index))
     }
 
     fn next_token(&mut self) -> TokenResult {
@@ -91,89 +336,122 @@ impl<'a> Tokenizer<'a> {
             None => return TokenResult::End,
         };
         if c == '#' {
-            if let Some(cr_idx) = self.remaining.find('\r') {
-                if let Some(nl_idx) = self.remaining.find('\n') {
-                    self.remaining = &self.remaining[nl_idx..];
-                    return TokenResult::Skip;
-                }
-                self.remaining = &self.remaining[cr_idx..];
-                return TokenResult::Skip;
-            } else {
-                if let Some(nl_idx) = self.remaining.find('\n') {
-                    self.remaining = &self.remaining[nl_idx..];
-                    return TokenResult::Skip;
-                }
+            // Comments run to the end of the line, leaving out the `\r` of a `\r\n`.
+            let mut length = self.remaining.find('\n').unwrap_or(self.remaining.len());
+            if self.remaining[..length].ends_with('\r') {
+                length -= 1;
             }
-
-            return TokenResult::End;
+            let range = self.index..self.index + length;
+            let doc = self.remaining.starts_with("##");
+            self.advance(length);
+            return if doc {
+                TokenResult::Token(Token {
+                    range,
+                    ty: TokenTy::DocComment,
+                })
+            } else {
+                TokenResult::Skip
+            };
         }
-        if c == ' ' || c == '\r' {
-            self.remaining = &self.remaining[1..];
-            self.increment_col(1);
+        if self.remaining.starts_with("/*") {
+            return match self.skip_block_comment() {
+                Ok(()) => TokenResult::Skip,
+                Err(error) => TokenResult::Error(error),
+            };
+        }
+        if c == ' ' || c == '\r' || c == '\n' {
+            self.advance(1);
             return TokenResult::Skip;
         }
 
-        if c == '\n' {
-            self.remaining = &self.remaining[1..];
-            self.increment_line();
-            return TokenResult::Skip;
+        if c.is_ascii_digit() {
+            return match self.read_number() {
+                Ok((ty, range)) => TokenResult::Token(Token {
+                    range,
+                    ty: TokenTy::Literal(ty),
+                }),
+                Err(error) => TokenResult::Error(error),
+            };
         }
 
-        if c.is_digit(10) {
-            let number = self.read_number();
-            return TokenResult::Token(Token {
-                range: number,
-                ty: TokenTy::Literal(PrimitiveTy::Number),
-            });
+        if c == '"' {
+            return match self.read_quoted('"') {
+                Ok(range) => TokenResult::Token(Token {
+                    range,
+                    ty: TokenTy::Literal(PrimitiveTy::Str),
+                }),
+                Err(error) => TokenResult::Error(error),
+            };
         }
 
-        if SYMBOLS
-            .iter()
-            .any(|symbol| self.remaining.starts_with(symbol.0))
-        {
-            let symbol = self.read_symbol();
-            return TokenResult::Token(Token {
-                range: symbol.1,
-                ty: TokenTy::Symbol(symbol.0),
-            });
+        if c == '\'' {
+            let name_length: usize = self.remaining[1..]
+                .chars()
+                .take_while(|&id_c| id_c.is_alphanumeric() || id_c == '_')
+                .map(char::len_utf8)
+                .sum();
+            // `'a'` is a character literal, while `'a` without a closing quote is a
+            // label. Quoted words like `'ab'` and `''` are lexed as (invalid)
+            // character literals too, so they are reported as such.
+            let mut chars = self.remaining[1..].chars();
+            let is_char = match (chars.next(), chars.next()) {
+                (Some('\\' | '\''), _) => true,
+                (Some(_), Some('\'')) => true,
+                _ => name_length > 0 && self.remaining[1 + name_length..].starts_with('\''),
+            };
+            if is_char {
+                return match self.read_quoted('\'') {
+                    Ok(range) => TokenResult::Token(Token {
+                        range,
+                        ty: TokenTy::Literal(PrimitiveTy::Char),
+                    }),
+                    Err(error) => TokenResult::Error(error),
+                };
+            }
+            if name_length > 0 {
+                let start_index = self.index;
+                self.advance(name_length + 1);
+                return TokenResult::Token(Token {
+                    range: start_index..self.index,
+                    ty: TokenTy::Label,
+                });
+            }
         }
 
-        if KEYWORDS
-            .iter()
-            .any(|keyword| self.remaining.starts_with(keyword.0))
-        {
-            let keyword = self.read_keyword();
+        if let Some(symbol) = self.read_symbol() {
             return TokenResult::Token(Token {
-                range: keyword.1,
-                ty: TokenTy::Keyword(keyword.0),
+                range: symbol.1,
+                ty: TokenTy::Symbol(symbol.0),
             });
         }
 
-        let identifier_length = self
+        let identifier_length: usize = self
             .remaining
             .chars()
             .take_while(|&id_c| id_c.is_alphabetic() || id_c.is_numeric() || id_c == '_')
-            .count();
+            .map(char::len_utf8)
+            .sum();
         if identifier_length > 0 {
-            let start_index = self.index;
-            self.increment_char(identifier_length);
-            self.increment_col(identifier_length);
-            let range = start_index..start_index + identifier_length;
-            return TokenResult::Token(Token {
-                range,
-                ty: TokenTy::Identifier,
-            });
+            // Keywords are only recognized as whole words, so `letter` is one identifier.
+            let ty = match &self.remaining[..identifier_length] {
+                "true" | "false" => TokenTy::Literal(PrimitiveTy::Boolean),
+                text => match KEYWORDS.get(text) {
+                    Some(keyword) => TokenTy::Keyword(*keyword),
+                    None => TokenTy::Identifier,
+                },
+            };
+            let range = self.index..self.index + identifier_length;
+            self.advance(identifier_length);
+            return TokenResult::Token(Token { range, ty });
         }
 
-        self.increment_char(1);
-        self.increment_col(1);
-
-        TokenResult::InvalidCharacter(c)
+        self.advance(c.len_utf8());
+        TokenResult::InvalidCharacter
     }
 }
 
 impl<'a> Iterator for Tokenizer<'a> {
-    type Item = Result<Token, TextSection>;
+    type Item = Result<Token, LexError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let pre_line = self.line;
@@ -181,29 +459,24 @@ impl<'a> Iterator for Tokenizer<'a> {
         let pre_index = self.index;
         match self.next_token() {
             TokenResult::Token(token) => Some(Ok(token)),
-            TokenResult::Skip => return self.next(),
-            TokenResult::InvalidCharacter(_) => {
+            TokenResult::Skip => self.next(),
+            TokenResult::InvalidCharacter => {
+                // Report a run of invalid characters as a single error.
                 let mut copy = self.clone();
-
-                let mut length = 0;
-                // Yes this is terrible
-                while let TokenResult::InvalidCharacter(_) = copy.next_token() {
-                    length += 1;
+                while let TokenResult::InvalidCharacter = copy.next_token() {
+                    *self = copy.clone();
                 }
 
-                self.increment_char(length);
-                self.increment_col(length);
-
-                let post_line = self.line;
-                let post_col = self.column;
-                let post_index = self.index;
-
-                Some(Err(TextSection {
-                    index: pre_index..post_index,
-                    line: pre_line..post_line,
-                    column: pre_col..post_col,
+                Some(Err(LexError {
+                    section: TextSection {
+                        index: pre_index..self.index,
+                        line: pre_line..self.line,
+                        column: pre_col..self.column,
+                    },
+                    ty: LexErrorTy::InvalidCharacters,
                 }))
             }
+            TokenResult::Error(error) => Some(Err(error)),
             TokenResult::End => None,
         }
     }
@@ -6,14 +6,199 @@ pub mod codegen;
 
 #[derive(Debug, PartialEq)]
 pub struct TextSection {
+    /// Byte offsets into the source.
     pub index: Range<usize>,
     pub line: Range<usize>,
+    /// Offsets within the line in UTF-16 code units, which is one per character
+    /// outside of the astral planes. A `\r` right before a line break or the end
+    /// of the source isn't counted.
     pub column: Range<usize>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PrimitiveTy {
+    /// An integer literal without a suffix. It takes on whichever integer type
+    /// its context expects, and is an `i32` when nothing decides.
     Number,
+    Integer(IntegerTy),
+    /// A floating-point literal without a suffix. Like [`PrimitiveTy::Number`] it
+    /// takes on the float type its context expects, and is an `f64` otherwise.
+    Decimal,
+    Float(FloatTy),
     Boolean,
+    /// A Unicode scalar value.
+    Char,
+    Str,
     Void
 }
+
+impl PrimitiveTy {
+    /// Resolves a type name as written in source.
+    pub fn from_name(name: &str) -> Option<PrimitiveTy> {
+        match name {
+            "bool" => Some(PrimitiveTy::Boolean),
+            "char" => Some(PrimitiveTy::Char),
+            "str" => Some(PrimitiveTy::Str),
+            "f32" => Some(PrimitiveTy::Float(FloatTy::F32)),
+            "f64" => Some(PrimitiveTy::Float(FloatTy::F64)),
+            _ => IntegerTy::from_name(name).map(PrimitiveTy::Integer),
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        matches!(self, PrimitiveTy::Number | PrimitiveTy::Integer(_))
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, PrimitiveTy::Decimal | PrimitiveTy::Float(_))
+    }
+
+    /// Whether a value of this type can be used where `target` is expected.
+    pub fn coerces_to(self, target: PrimitiveTy) -> bool {
+        self == target
+            || self == PrimitiveTy::Number && matches!(target, PrimitiveTy::Integer(_))
+            || self == PrimitiveTy::Decimal && matches!(target, PrimitiveTy::Float(_))
+    }
+
+    /// The type a value is stored as, which is `i32` or `f64` for an unsuffixed
+    /// literal.
+    pub fn defaulted(self) -> PrimitiveTy {
+        match self {
+            PrimitiveTy::Number => PrimitiveTy::Integer(IntegerTy::I32),
+            PrimitiveTy::Decimal => PrimitiveTy::Float(FloatTy::F64),
+            ty => ty,
+        }
+    }
+}
+
+impl std::fmt::Display for PrimitiveTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrimitiveTy::Number => write!(f, "integer"),
+            PrimitiveTy::Integer(ty) => write!(f, "{}", ty.name()),
+            PrimitiveTy::Decimal => write!(f, "float"),
+            PrimitiveTy::Float(FloatTy::F32) => write!(f, "f32"),
+            PrimitiveTy::Float(FloatTy::F64) => write!(f, "f64"),
+            PrimitiveTy::Boolean => write!(f, "bool"),
+            PrimitiveTy::Char => write!(f, "char"),
+            PrimitiveTy::Str => write!(f, "str"),
+            PrimitiveTy::Void => write!(f, "void"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IntegerTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+}
+
+impl IntegerTy {
+    pub const ALL: [IntegerTy; 10] = [
+        IntegerTy::I8,
+        IntegerTy::I16,
+        IntegerTy::I32,
+        IntegerTy::I64,
+        IntegerTy::Isize,
+        IntegerTy::U8,
+        IntegerTy::U16,
+        IntegerTy::U32,
+        IntegerTy::U64,
+        IntegerTy::Usize,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntegerTy::I8 => "i8",
+            IntegerTy::I16 => "i16",
+            IntegerTy::I32 => "i32",
+            IntegerTy::I64 => "i64",
+            IntegerTy::Isize => "isize",
+            IntegerTy::U8 => "u8",
+            IntegerTy::U16 => "u16",
+            IntegerTy::U32 => "u32",
+            IntegerTy::U64 => "u64",
+            IntegerTy::Usize => "usize",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntegerTy> {
+        IntegerTy::ALL.into_iter().find(|ty| ty.name() == name)
+    }
+
+    /// Size in bytes, with `isize`/`usize` being pointer sized on x86-64.
+    pub fn size(self) -> u32 {
+        match self {
+            IntegerTy::I8 | IntegerTy::U8 => 1,
+            IntegerTy::I16 | IntegerTy::U16 => 2,
+            IntegerTy::I32 | IntegerTy::U32 => 4,
+            IntegerTy::I64 | IntegerTy::U64 | IntegerTy::Isize | IntegerTy::Usize => 8,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntegerTy::I8 | IntegerTy::I16 | IntegerTy::I32 | IntegerTy::I64 | IntegerTy::Isize
+        )
+    }
+
+    /// Smallest and largest representable values.
+    pub fn range(self) -> (i128, i128) {
+        let bits = self.size() * 8;
+        if self.is_signed() {
+            (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+        } else {
+            (0, (1 << bits) - 1)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FloatTy {
+    F32,
+    F64,
+}
+
+impl FloatTy {
+    /// Size in bytes.
+    pub fn size(self) -> u32 {
+        match self {
+            FloatTy::F32 => 4,
+            FloatTy::F64 => 8,
+        }
+    }
+}
+
+impl TextSection {
+    /// Computes the line and column span of the byte range `index` within `source`.
+    pub fn from_index(source: &str, index: Range<usize>) -> TextSection {
+        let position = |offset: usize| {
+            let before = &source[..offset];
+            let line = before.matches('\n').count();
+            let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+            let mut text = &before[line_start..];
+            let rest = &source[offset..];
+            if rest.is_empty() || rest.starts_with('\n') {
+                text = text.strip_suffix('\r').unwrap_or(text);
+            }
+            let column: usize = text.chars().map(char::len_utf16).sum();
+            (line, column)
+        };
+        let (start_line, start_column) = position(index.start);
+        let (end_line, end_column) = position(index.end);
+        TextSection {
+            index,
+            line: start_line..end_line,
+            column: start_column..end_column,
+        }
+    }
+}
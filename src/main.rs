@@ -1,266 +1,386 @@
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::{Command, exit};
-use deltac::codegen::Generator;
+use deltac::lexer::token::Token;
 use deltac::lexer::tokenizer::Tokenizer;
-use deltac::parse::{BinaryOperationTy, ControlFlow, Expression, Operation, Statement};
-use deltac::PrimitiveTy;
+use deltac::parse::Module;
+use deltac::TextSection;
 
-fn main() {
-    let input = r#"
-fn main() {
-    let a = 10;
-    if a > 5 {
-        a = 5;
+const USAGE: &str = "\
+Usage: deltac [options] <input>...
+       deltac run [options] <input>... [-- <program args>...]
+
+Options:
+    -o <output>     Write the output to <output>
+    --emit=<kind>   Output kind: tokens, ast, asm, obj or exe (default: exe)
+    -h, --help      Print this message";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Emit {
+    Tokens,
+    Ast,
+    Asm,
+    Obj,
+    Exe,
+}
+
+#[derive(Debug)]
+struct Options {
+    run: bool,
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    emit: Emit,
+    program_args: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        run: false,
+        inputs: Vec::new(),
+        output: None,
+        emit: Emit::Exe,
+        program_args: Vec::new(),
+    };
+    let mut first = true;
+    while let Some(arg) = args.next() {
+        if first && arg == "run" {
+            options.run = true;
+            first = false;
+            continue;
+        }
+        first = false;
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            "-o" => {
+                let output = args.next().ok_or("`-o` requires an argument")?;
+                options.output = Some(output.into());
+            }
+            "--" if options.run => {
+                options.program_args.extend(args.by_ref());
+            }
+            _ if arg.starts_with("--emit=") => {
+                options.emit = match &arg["--emit=".len()..] {
+                    "tokens" => Emit::Tokens,
+                    "ast" => Emit::Ast,
+                    "asm" => Emit::Asm,
+                    "obj" => Emit::Obj,
+                    "exe" => Emit::Exe,
+                    kind => return Err(format!("unknown emit kind `{}`", kind)),
+                };
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            _ => options.inputs.push(arg.into()),
+        }
+    }
+    if options.inputs.is_empty() {
+        return Err("no input files".to_owned());
+    }
+    if options.run && options.emit != Emit::Exe {
+        return Err("`run` cannot be combined with `--emit`".to_owned());
+    }
+    Ok(options)
+}
+
+fn report_error(path: &Path, input: &str, section: &TextSection, message: &str) {
+    if section.line.end != section.line.start {
+        eprintln!(
+            "{}: {} between {}:{} and {}:{} `{}`",
+            path.display(),
+            message,
+            section.line.start + 1,
+            section.column.start + 1,
+            section.line.end + 1,
+            section.column.end + 1,
+            &input[section.index.clone()]
+        )
+    } else {
+        let line_start = if section.line.start == 0 {
+            0
+        } else {
+            input
+                .match_indices('\n')
+                .nth(section.line.start - 1)
+                .unwrap()
+                .0
+                + 1
+        };
+        let line_end = match input.match_indices('\n').nth(section.line.start) {
+            Some((n, _)) => n,
+            None => input.len(),
+        };
+        eprintln!(
+            "\u{001b}[31mError at {}:{}: {}",
+            path.display(),
+            section.line.start + 1,
+            message
+        );
+        eprintln!(
+            "| \u{001b}[0m{}",
+            input[line_start..line_end].trim_end_matches('\r')
+        );
+        // Columns are in UTF-16 units, so the marker is placed by counting chars.
+        let start = section.index.start.clamp(line_start, line_end);
+        let end = section.index.end.clamp(start, line_end);
+        let padding = input[line_start..start].chars().count();
+        let width = input[start..end].chars().count();
+        eprint!("\u{001b}[34m");
+        for _ in 0..padding + 2 {
+            eprint!(" ");
+        }
+        if width > 1 {
+            eprint!("[");
+            for _ in 0..width - 2 {
+                eprint!("-");
+            }
+            eprint!("]");
+        } else {
+            eprint!("^")
+        }
+        eprintln!("\u{001b}[0m");
     }
-    # return a;
-}"#;
-    let tokenizer = Tokenizer::new(input);
+}
+
+fn tokenize(path: &Path, input: &str) -> Option<Vec<Token>> {
     let mut tokens = Vec::new();
-    for result in tokenizer {
+    let mut failed = false;
+    for result in Tokenizer::new(input) {
         match result {
             Ok(token) => tokens.push(token),
-            Err(section) => {
-                if section.line.end != section.line.start {
-                    println!(
-                        "Invalid characters between {}:{} and {}:{} `{}`",
-                        section.line.start,
-                        section.column.start,
-                        section.line.end,
-                        section.column.end,
-                        &input[section.index]
-                    )
-                } else {
-                    let line_start = if section.line.start == 0 {
-                        0
-                    } else {
-                        input
-                            .match_indices('\n')
-                            .nth(section.line.start - 1)
-                            .unwrap()
-                            .0
-                            + 1
-                    };
-                    let line_end = match input.match_indices('\n').nth(section.line.start) {
-                        Some((n, _)) => n,
-                        None => input.len(),
-                    };
-                    println!("\u{001b}[31mError at line {}", section.line.start + 1);
-                    println!("| \u{001b}[0m{}", &input[line_start..line_end]);
-                    print!("\u{001b}[34m");
-                    for _ in 0..section.column.start + 2 {
-                        print!(" ");
-                    }
-                    if section.column.end - section.column.start > 1 {
-                        print!("[");
-                        for _ in 0..section.column.end - section.column.start - 2 {
-                            print!("-");
-                        }
-                        print!("] these characters are invalid.");
-                    } else {
-                        print!("^ this character is invalid.")
-                    }
-                    println!("\u{001b}[0m");
-                    /*println!(
-                        "Invalid characters at line {} between columns {} and {} `{}`",
-                        section.line.start,
-                        section.column.start,
-                        section.column.end,
-                        &input[section.index]
-                    )*/
-                }
+            Err(error) => {
+                report_error(path, input, &error.section, &error.ty.to_string());
+                failed = true;
             }
         }
     }
-    let parser = deltac::parse::Parser::new(input, &tokens);
-    let module = parser.parse();
-
-    let mut gen = Generator::new();
-    gen.raw(".global main");
-    gen.raw(".text");
+    if failed {
+        None
+    } else {
+        Some(tokens)
+    }
+}
 
-    for func in module.functions {
-        let func_name = if func.name == "main" {
-            "main".to_owned()
-        } else {
-            format!("deltafunc_{}", func.name)
-        };
-        gen.label(&func_name);
+/// Assembles `asm` with gcc, producing an object file when `object` is set and an executable otherwise.
+fn assemble(asm: &str, output: &Path, object: bool) -> Result<(), String> {
+    let asm_path = std::env::temp_dir().join(format!("deltac-{}.s", std::process::id()));
+    std::fs::write(&asm_path, asm)
+        .map_err(|e| format!("could not write `{}`: {}", asm_path.display(), e))?;
+    let mut gcc = Command::new("gcc");
+    if object {
+        gcc.arg("-c");
+    } else {
+        gcc.arg("-no-pie");
+    }
+    let status = gcc.arg(&asm_path).arg("-o").arg(output).status();
+    let _ = std::fs::remove_file(&asm_path);
+    let status = status.map_err(|e| format!("could not run gcc: {}", e))?;
+    if !status.success() {
+        return Err(format!("gcc failed with {}", status));
+    }
+    Ok(())
+}
 
-        struct LocalVar {
-            stack_index: u32,
-            ty: PrimitiveTy,
+/// Writes the tokens or AST to the `-o` path, or to stdout without one.
+fn write_dump(options: &Options, dump: &str) {
+    match &options.output {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, dump) {
+                eprintln!("error: could not write `{}`: {}", output.display(), e);
+                exit(1);
+            }
         }
+        None => print!("{}", dump),
+    }
+}
 
-        // how to do dynamic locals??
-        let mut locals: HashMap<&str, LocalVar> = HashMap::new();
-        {
-            let mut stack_counter = 8; // Offset by 8 for return value
-            for stmt in &func.body {
-                match stmt {
-                    Statement::Declaration { name, expression } => {
-                        let ty = expression.get_type(&|n| locals[n].ty);
-                        locals.insert(name, LocalVar {
-                            stack_index: stack_counter,
-                            ty
-                        });
-                        stack_counter += match ty {
-                            // Assume u32 for now
-                            PrimitiveTy::Number => {
-                                4
-                            }
-                            PrimitiveTy::Boolean => {
-                                1
-                            }
-                            PrimitiveTy::Void => {
-                                0
-                            },
-                        };
-                    }
-                    _ => {},
-                }
+fn default_output(options: &Options) -> PathBuf {
+    let stem = options.inputs[0]
+        .file_stem()
+        .map(|s| s.to_owned())
+        .unwrap_or_else(|| "a".into());
+    let mut output = PathBuf::from(stem);
+    match options.emit {
+        Emit::Asm => {
+            output.set_extension("s");
+        }
+        Emit::Obj => {
+            output.set_extension("o");
+        }
+        _ => {
+            if output == options.inputs[0] {
+                output = PathBuf::from("a.out");
             }
+        }
+    }
+    output
+}
 
-            stack_counter += 12; // Alignment
-            stack_counter -= 8; // Refix original offset
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            exit(2);
+        }
+    };
 
-            gen.raw(format!("sub ${}, %rsp", stack_counter));
+    let mut sources = Vec::new();
+    for path in &options.inputs {
+        match std::fs::read_to_string(path) {
+            Ok(source) => sources.push(source),
+            Err(e) => {
+                eprintln!("error: could not read `{}`: {}", path.display(), e);
+                exit(1);
+            }
         }
+    }
 
-        let branch_counter = 0;
+    let mut token_lists = Vec::new();
+    for (path, source) in options.inputs.iter().zip(&sources) {
+        token_lists.push(tokenize(path, source));
+    }
+    let token_lists = match token_lists.into_iter().collect::<Option<Vec<_>>>() {
+        Some(token_lists) => token_lists,
+        None => exit(1),
+    };
 
-        for stmt in func.body {
-            fn gen_expr<'a, 'b>(gen: &'a mut Generator, locals: &'a HashMap<&'b str, LocalVar>, expr: &'b Expression, out: Option<&'a LocalVar>) {
-                match expr {
-                    Expression::Variable(var) => {
-                        if let Some(out) = out {
-                            let var = &locals[var];
-                            gen.raw(format!("movl -{}(%rsp), -{}(%rsp)", var.stack_index, out.stack_index));
-                        }
-                    }
-                    Expression::Literal(lit) => {
-                        if let Some(out) = out {
-                            assert_eq!(out.ty, lit.ty);
-                            match lit.ty {
-                                PrimitiveTy::Number => {
-                                    let v = lit.value.parse::<u32>().unwrap();
-                                    gen.raw(format!("movl ${}, -{}(%rsp)", v, out.stack_index));
-                                }
-                                PrimitiveTy::Boolean => {
-                                    let v: u32 = if lit.value.parse::<bool>().unwrap() {
-                                        1
-                                    } else {
-                                        0
-                                    };
-                                    gen.raw(format!("movb ${}, -{}(%rsp)", v, out.stack_index));
-                                }
-                                PrimitiveTy::Void => {
-                                    panic!()
-                                }
-                            }
-                        }
-                    }
-                    Expression::Operation(op) => {
-                        match &**op {
-                            Operation::Binary { left, ty, right } => {
-                                match ty {
-                                    BinaryOperationTy::Assignment => {
-                                        match left {
-                                            Expression::Variable(var) => {
-                                                gen_expr(gen, locals, right, Some(&locals[var]));
-                                            }
-                                            _ => todo!()
-                                        }
-                                    }
-                                    _ => todo!()
-                                }
-                            }
-                        }
-                    }
-                }
+    if options.emit == Emit::Tokens {
+        let mut dump = String::new();
+        for (source, tokens) in sources.iter().zip(&token_lists) {
+            for token in tokens {
+                dump.push_str(&format!("{:?} `{}`\n", token.ty, &source[token.range.clone()]));
             }
-            match stmt {
-                Statement::Declaration { name, expression } => {
-                    gen_expr(&mut gen, &locals, &expression, Some(&locals[name]));
+        }
+        write_dump(&options, &dump);
+        return;
+    }
+
+    let mut module = Module {
+        functions: Vec::new(),
+    };
+    let mut failed = false;
+    for ((path, source), tokens) in options.inputs.iter().zip(&sources).zip(&token_lists) {
+        match deltac::parse::Parser::new(source, tokens).parse() {
+            Ok(parsed) => module.functions.extend(parsed.functions),
+            Err(errors) => {
+                for error in errors {
+                    report_error(path, source, &error.section, &error.message);
                 }
-                Statement::Expression(expr) => gen_expr(&mut gen, &locals, &expr, None),
-                Statement::ControlFlow(ctrl) => {
-                    match ctrl {
-                        ControlFlow::If { condition, body } => {
-                            assert_eq!(condition.get_type(&|n| locals[n].ty), PrimitiveTy::Boolean);
-                            match condition {
-                                Expression::Variable(_var) => todo!(),
-                                Expression::Literal(_lit) => todo!(),
-                                Expression::Operation(op) => {
-                                    match &*op {
-                                        Operation::Binary { left, ty, right } => {
-                                            let l_type = left.get_type(&|n| locals[n].ty);
-                                            assert_eq!(l_type, right.get_type(&|n| locals[n].ty));
-                                            assert_eq!(l_type, PrimitiveTy::Number);
-                                            let gen_ref = |expr: &Expression| {
-                                                match expr {
-                                                    Expression::Variable(var) => format!("-{}(%rsp)", locals[var].stack_index),
-                                                    Expression::Literal(lit) => {
-                                                        assert_eq!(lit.ty, PrimitiveTy::Number);
-                                                        format!("${}", lit.value)
-                                                    },
-                                                    Expression::Operation(_op) => todo!(),
-                                                }
-                                            };
-                                            let l_ref = gen_ref(left);
-                                            let r_ref = gen_ref(right);
-                                            gen.raw(format!("cmpl {}, {}", r_ref, l_ref));
-                                            let branch_name = format!("{}_{}", func_name, branch_counter);
-                                            match ty {
-                                                BinaryOperationTy::Greater => {
-                                                    gen.raw(format!("jle {}", branch_name));
-                                                }
-                                                _ => panic!(),
-                                            }
-                                            for stmt in body {
-                                                match stmt {
-                                                    Statement::Expression(expr) => gen_expr(&mut gen, &locals, &expr, None),
-                                                    _ => todo!()
-                                                }
-                                            }
-                                            gen.label(branch_name);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                },
+                failed = true;
             }
         }
+    }
+    if failed {
+        exit(1);
+    }
 
-        gen.raw("mov $format, %rdi");
-        gen.raw(format!("mov -{}(%rsp), %rsi", locals["a"].stack_index));
-        gen.raw("xor %rax, %rax");
-        gen.raw("call printf");
+    if options.emit == Emit::Ast {
+        write_dump(&options, &format!("{:#?}\n", module));
+        return;
+    }
 
-        if func.name == "main" {
-            gen.exit(0);
+    let asm = match deltac::codegen::compile_module(&module) {
+        Ok(asm) => asm,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(1);
         }
+    };
+
+    let result = match options.emit {
+        Emit::Asm => {
+            let output = options.output.clone().unwrap_or_else(|| default_output(&options));
+            std::fs::write(&output, &asm)
+                .map_err(|e| format!("could not write `{}`: {}", output.display(), e))
+        }
+        Emit::Obj => {
+            let output = options.output.clone().unwrap_or_else(|| default_output(&options));
+            assemble(&asm, &output, true)
+        }
+        Emit::Exe if options.run => {
+            let output = options.output.clone().unwrap_or_else(|| {
+                std::env::temp_dir().join(format!("deltac-run-{}", std::process::id()))
+            });
+            assemble(&asm, &output, false).and_then(|()| {
+                let status = Command::new(&output)
+                    .args(&options.program_args)
+                    .status()
+                    .map_err(|e| format!("could not run `{}`: {}", output.display(), e));
+                if options.output.is_none() {
+                    let _ = std::fs::remove_file(&output);
+                }
+                // Forward the program's exit code, treating death by signal as a failure.
+                exit(status?.code().unwrap_or(1));
+            })
+        }
+        Emit::Exe => {
+            let output = options.output.clone().unwrap_or_else(|| default_output(&options));
+            assemble(&asm, &output, false)
+        }
+        Emit::Tokens | Emit::Ast => unreachable!(),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("error: {}", msg);
+        exit(1);
     }
+}
 
-    gen.label_with_value("format", ".asciz \"Value: %d\\n\"");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let full_raw = gen.full_raw();
-    std::fs::write("gen.s", full_raw).unwrap();
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_run() {
+        let options = parse(&["run", "a.dl", "b.dl", "--", "x", "-o"]).unwrap();
+        assert!(options.run);
+        assert_eq!(options.inputs, vec![PathBuf::from("a.dl"), PathBuf::from("b.dl")]);
+        assert_eq!(options.program_args, vec!["x", "-o"]);
+        assert_eq!(options.emit, Emit::Exe);
+        // `run` is only a subcommand in first position.
+        let options = parse(&["a.dl", "run"]).unwrap();
+        assert!(!options.run);
+        assert_eq!(options.inputs, vec![PathBuf::from("a.dl"), PathBuf::from("run")]);
+    }
 
-    let gcc_status = Command::new("gcc").arg("-no-pie").arg("gen.s").arg("-o").arg("a.out").status().unwrap();
-    let gcc_code = gcc_status.code().unwrap();
-    println!("GCC: {}", gcc_code);
-    if gcc_code != 0 {
-        exit(gcc_code);
+    #[test]
+    fn parse_emit() {
+        let options = parse(&["--emit=ast", "-o", "out.ast", "a.dl"]).unwrap();
+        assert_eq!(options.emit, Emit::Ast);
+        assert_eq!(options.output, Some(PathBuf::from("out.ast")));
+        for (kind, emit) in [
+            ("tokens", Emit::Tokens),
+            ("asm", Emit::Asm),
+            ("obj", Emit::Obj),
+            ("exe", Emit::Exe),
+        ] {
+            let arg = format!("--emit={}", kind);
+            assert_eq!(parse(&[&arg, "a.dl"]).unwrap().emit, emit);
+        }
     }
-    println!("Running program!\n");
 
-    let proc = Command::new("./a.out").spawn().unwrap();
-    let output = proc.wait_with_output().unwrap();
-    let proc_code = output.status.code().unwrap();
-    println!("Exit Code: {}", proc_code);
-    if proc_code != 0 {
-        exit(proc_code);
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(&[]).unwrap_err(), "no input files");
+        assert_eq!(parse(&["run"]).unwrap_err(), "no input files");
+        assert_eq!(parse(&["a.dl", "-o"]).unwrap_err(), "`-o` requires an argument");
+        assert_eq!(
+            parse(&["--emit=wasm", "a.dl"]).unwrap_err(),
+            "unknown emit kind `wasm`"
+        );
+        assert_eq!(parse(&["-x", "a.dl"]).unwrap_err(), "unknown option `-x`");
+        assert_eq!(
+            parse(&["run", "--emit=asm", "a.dl"]).unwrap_err(),
+            "`run` cannot be combined with `--emit`"
+        );
+        // `--` only separates program arguments after `run`.
+        assert_eq!(parse(&["a.dl", "--"]).unwrap_err(), "unknown option `--`");
     }
 }
@@ -1,39 +1,259 @@
 use std::rc::Rc;
 
+use itertools::Itertools;
+
 use crate::{
-    lexer::token::{KeywordTy, SymbolTy, Token, TokenTy},
-    PrimitiveTy,
+    lexer::{
+        token::{KeywordTy, SymbolTy, Token, TokenTy},
+        tokenizer::{parse_integer, unescape},
+    },
+    IntegerTy, PrimitiveTy, TextSection,
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BinaryOperationTy {
     Addition,
+    Subtraction,
+    Multiplication,
+    Division,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
     Greater,
+    GreaterEqual,
+    LogicalAnd,
+    LogicalOr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Assignment,
 }
 
-/*#[derive(Debug)]
+impl BinaryOperationTy {
+    fn from_token(ty: TokenTy) -> Option<Self> {
+        let symbol = match ty {
+            TokenTy::Symbol(symbol) => symbol,
+            _ => return None,
+        };
+        Some(match symbol {
+            SymbolTy::Add => BinaryOperationTy::Addition,
+            SymbolTy::Sub => BinaryOperationTy::Subtraction,
+            SymbolTy::Mul => BinaryOperationTy::Multiplication,
+            SymbolTy::Div => BinaryOperationTy::Division,
+            SymbolTy::Rem => BinaryOperationTy::Remainder,
+            SymbolTy::Equal => BinaryOperationTy::Equal,
+            SymbolTy::NotEqual => BinaryOperationTy::NotEqual,
+            SymbolTy::Less => BinaryOperationTy::Less,
+            SymbolTy::LessEqual => BinaryOperationTy::LessEqual,
+            SymbolTy::Greater => BinaryOperationTy::Greater,
+            SymbolTy::GreaterEqual => BinaryOperationTy::GreaterEqual,
+            SymbolTy::LogicalAnd => BinaryOperationTy::LogicalAnd,
+            SymbolTy::LogicalOr => BinaryOperationTy::LogicalOr,
+            SymbolTy::BitAnd => BinaryOperationTy::BitAnd,
+            SymbolTy::BitOr => BinaryOperationTy::BitOr,
+            SymbolTy::BitXor => BinaryOperationTy::BitXor,
+            SymbolTy::ShiftLeft => BinaryOperationTy::ShiftLeft,
+            SymbolTy::ShiftRight => BinaryOperationTy::ShiftRight,
+            SymbolTy::Assign => BinaryOperationTy::Assignment,
+            _ => return None,
+        })
+    }
+
+    /// The operator applied by a compound assignment token such as `+=`.
+    fn from_compound_token(ty: TokenTy) -> Option<Self> {
+        let symbol = match ty {
+            TokenTy::Symbol(symbol) => symbol,
+            _ => return None,
+        };
+        Some(match symbol {
+            SymbolTy::AddAssign => BinaryOperationTy::Addition,
+            SymbolTy::SubAssign => BinaryOperationTy::Subtraction,
+            SymbolTy::MulAssign => BinaryOperationTy::Multiplication,
+            SymbolTy::DivAssign => BinaryOperationTy::Division,
+            SymbolTy::RemAssign => BinaryOperationTy::Remainder,
+            SymbolTy::BitAndAssign => BinaryOperationTy::BitAnd,
+            SymbolTy::BitOrAssign => BinaryOperationTy::BitOr,
+            SymbolTy::BitXorAssign => BinaryOperationTy::BitXor,
+            SymbolTy::ShiftLeftAssign => BinaryOperationTy::ShiftLeft,
+            SymbolTy::ShiftRightAssign => BinaryOperationTy::ShiftRight,
+            _ => return None,
+        })
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperationTy::Addition => "+",
+            BinaryOperationTy::Subtraction => "-",
+            BinaryOperationTy::Multiplication => "*",
+            BinaryOperationTy::Division => "/",
+            BinaryOperationTy::Remainder => "%",
+            BinaryOperationTy::Equal => "==",
+            BinaryOperationTy::NotEqual => "!=",
+            BinaryOperationTy::Less => "<",
+            BinaryOperationTy::LessEqual => "<=",
+            BinaryOperationTy::Greater => ">",
+            BinaryOperationTy::GreaterEqual => ">=",
+            BinaryOperationTy::LogicalAnd => "&&",
+            BinaryOperationTy::LogicalOr => "||",
+            BinaryOperationTy::BitAnd => "&",
+            BinaryOperationTy::BitOr => "|",
+            BinaryOperationTy::BitXor => "^",
+            BinaryOperationTy::ShiftLeft => "<<",
+            BinaryOperationTy::ShiftRight => ">>",
+            BinaryOperationTy::Assignment => "=",
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperationTy::Equal
+                | BinaryOperationTy::NotEqual
+                | BinaryOperationTy::Less
+                | BinaryOperationTy::LessEqual
+                | BinaryOperationTy::Greater
+                | BinaryOperationTy::GreaterEqual
+        )
+    }
+
+    /// Left and right binding power of the operator, operators with a higher
+    /// power bind tighter. From loosest to tightest:
+    ///
+    /// | Operators                     | Associativity |
+    /// |-------------------------------|---------------|
+    /// | `=` `+=` `-=` `*=` `/=` `%=`  | right         |
+    /// | `&=` `\|=` `^=` `<<=` `>>=`   |               |
+    /// | `\|\|`                        | left          |
+    /// | `&&`                          | left          |
+    /// | `==` `!=` `<` `<=` `>` `>=`   | left          |
+    /// | `\|`                          | left          |
+    /// | `^`                           | left          |
+    /// | `&`                           | left          |
+    /// | `<<` `>>`                     | left          |
+    /// | `+` `-`                       | left          |
+    /// | `*` `/` `%`                   | left          |
+    /// | unary `-` `!`                 | prefix        |
+    ///
+    /// Left-associative operators have a higher right power so that an operator of
+    /// the same level ends the right operand, right-associative ones the opposite.
+    pub fn binding_power(&self) -> (u8, u8) {
+        match self {
+            BinaryOperationTy::Assignment => (2, 1),
+            BinaryOperationTy::LogicalOr => (3, 4),
+            BinaryOperationTy::LogicalAnd => (5, 6),
+            BinaryOperationTy::Equal
+            | BinaryOperationTy::NotEqual
+            | BinaryOperationTy::Less
+            | BinaryOperationTy::LessEqual
+            | BinaryOperationTy::Greater
+            | BinaryOperationTy::GreaterEqual => (7, 8),
+            BinaryOperationTy::BitOr => (9, 10),
+            BinaryOperationTy::BitXor => (11, 12),
+            BinaryOperationTy::BitAnd => (13, 14),
+            BinaryOperationTy::ShiftLeft | BinaryOperationTy::ShiftRight => (15, 16),
+            BinaryOperationTy::Addition | BinaryOperationTy::Subtraction => (17, 18),
+            BinaryOperationTy::Multiplication
+            | BinaryOperationTy::Division
+            | BinaryOperationTy::Remainder => (19, 20),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum UnaryOperationTy {
     Negate,
-}*/
+    Not,
+}
+
+impl UnaryOperationTy {
+    fn from_token(ty: TokenTy) -> Option<Self> {
+        match ty {
+            TokenTy::Symbol(SymbolTy::Sub) => Some(UnaryOperationTy::Negate),
+            TokenTy::Symbol(SymbolTy::Not) => Some(UnaryOperationTy::Not),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperationTy::Negate => "-",
+            UnaryOperationTy::Not => "!",
+        }
+    }
+
+    /// Binding power of the operand, tighter than any binary operator.
+    pub fn binding_power(&self) -> u8 {
+        23
+    }
+}
+
+/// Left binding power of `as`, which binds tighter than any binary operator but
+/// looser than the unary ones, so `-x as i64` casts `-x`.
+const CAST_BINDING_POWER: u8 = 21;
 
 #[derive(Debug, PartialEq)]
 pub enum Operation<'a> {
-    /*Unary {
-        target: Expresssion,
+    Unary {
+        target: Expression<'a>,
         ty: UnaryOperationTy,
-    },*/
+    },
     Binary {
         left: Expression<'a>,
         ty: BinaryOperationTy,
         right: Expression<'a>,
     },
+    /// `target op= value`, which applies `ty` to the target in place.
+    CompoundAssignment {
+        target: Expression<'a>,
+        ty: BinaryOperationTy,
+        value: Expression<'a>,
+    },
+    /// `target as ty`.
+    Cast {
+        target: Expression<'a>,
+        ty: Type<'a>,
+    },
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Literal<'a> {
+    /// The literal as written in source.
     pub value: &'a str,
     pub ty: PrimitiveTy,
+    /// The value of an integer, string or char literal, already checked by the
+    /// tokenizer.
+    pub parsed: Option<ParsedLiteral>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParsedLiteral {
+    Integer(u64),
+    /// The unescaped contents of a string or char literal.
+    Text(String),
+}
+
+impl ParsedLiteral {
+    fn new(value: &str, ty: PrimitiveTy) -> Option<ParsedLiteral> {
+        match ty {
+            PrimitiveTy::Number | PrimitiveTy::Integer(_) => {
+                parse_integer(value).ok().map(ParsedLiteral::Integer)
+            }
+            PrimitiveTy::Str | PrimitiveTy::Char => unescape(&value[1..value.len() - 1])
+                .ok()
+                .map(ParsedLiteral::Text),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Call<'a> {
+    pub name: &'a str,
+    pub arguments: Vec<Expression<'a>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -41,25 +261,141 @@ pub enum Expression<'a> {
     Variable(&'a str),
     Literal(Literal<'a>),
     Operation(Rc<Operation<'a>>),
+    Call(Call<'a>),
+}
+
+/// An error found while resolving the type of an [`Expression`].
+#[derive(Debug, PartialEq)]
+pub enum TypeError {
+    UnknownVariable(String),
+    UnknownFunction(String),
+    UnknownType(String),
+    ArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    Mismatch {
+        expected: PrimitiveTy,
+        found: PrimitiveTy,
+    },
+    InvalidOperand {
+        operator: &'static str,
+        ty: PrimitiveTy,
+    },
+    InvalidCast {
+        from: PrimitiveTy,
+        to: PrimitiveTy,
+    },
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeError::UnknownVariable(name) => write!(f, "unknown variable `{}`", name),
+            TypeError::UnknownFunction(name) => write!(f, "unknown function `{}`", name),
+            TypeError::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            TypeError::ArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "function `{}` takes {} argument(s) but {} were supplied",
+                function, expected, found
+            ),
+            TypeError::Mismatch { expected, found } => {
+                write!(f, "expected type `{}`, found `{}`", expected, found)
+            }
+            TypeError::InvalidOperand { operator, ty } => {
+                write!(f, "operator `{}` cannot be applied to `{}`", operator, ty)
+            }
+            TypeError::InvalidCast { from, to } => {
+                write!(f, "cannot cast `{}` as `{}`", from, to)
+            }
+        }
+    }
+}
+
+/// Resolved argument and return types of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub arguments: Vec<PrimitiveTy>,
+    pub return_type: PrimitiveTy,
+}
+
+/// The variables and functions visible to an expression being type checked.
+pub trait Scope {
+    fn variable(&self, name: &str) -> Option<PrimitiveTy>;
+    fn function(&self, name: &str) -> Option<&Signature>;
 }
 
 impl<'a> Expression<'a> {
-    pub fn get_type(&self, f: &impl Fn(&str) -> PrimitiveTy) -> PrimitiveTy {
+    pub fn get_type(&self, scope: &impl Scope) -> Result<PrimitiveTy, TypeError> {
         match self {
-            Expression::Variable(var) => f(var),
-            Expression::Literal(lit) => lit.ty,
+            Expression::Variable(var) => scope
+                .variable(var)
+                .ok_or_else(|| TypeError::UnknownVariable(var.to_string())),
+            Expression::Literal(lit) => Ok(lit.ty),
+            Expression::Call(call) => {
+                let signature = scope
+                    .function(call.name)
+                    .ok_or_else(|| TypeError::UnknownFunction(call.name.to_owned()))?;
+                if signature.arguments.len() != call.arguments.len() {
+                    return Err(TypeError::ArgumentCount {
+                        function: call.name.to_owned(),
+                        expected: signature.arguments.len(),
+                        found: call.arguments.len(),
+                    });
+                }
+                for (&expected, argument) in signature.arguments.iter().zip(&call.arguments) {
+                    let found = argument.get_type(scope)?;
+                    if !found.coerces_to(expected) {
+                        return Err(TypeError::Mismatch { expected, found });
+                    }
+                }
+                Ok(signature.return_type)
+            }
             Expression::Operation(op) => {
                 match &**op {
-                    Operation::Binary { left, ty, right } => {
-                        match ty {
-                            BinaryOperationTy::Addition => {
-                                let l_type = left.get_type(f);
-                                assert_eq!(l_type, right.get_type(f));
-                                l_type
+                    Operation::Unary { target, ty } => {
+                        let t_type = target.get_type(scope)?;
+                        match (ty, t_type) {
+                            (UnaryOperationTy::Negate, PrimitiveTy::Number)
+                            | (UnaryOperationTy::Not, PrimitiveTy::Number | PrimitiveTy::Boolean) => {
+                                Ok(t_type)
+                            }
+                            (UnaryOperationTy::Negate, PrimitiveTy::Integer(int)) if int.is_signed() => {
+                                Ok(t_type)
                             }
-                            BinaryOperationTy::Greater => PrimitiveTy::Boolean,
-                            BinaryOperationTy::Assignment => PrimitiveTy::Void,
+                            (UnaryOperationTy::Negate, ty) if ty.is_float() => Ok(t_type),
+                            (UnaryOperationTy::Not, PrimitiveTy::Integer(_)) => Ok(t_type),
+                            _ => Err(TypeError::InvalidOperand {
+                                operator: ty.symbol(),
+                                ty: t_type,
+                            }),
+                        }
+                    }
+                    Operation::Binary { left, ty, right } => {
+                        binary_type(*ty, left.get_type(scope)?, right.get_type(scope)?)
+                    }
+                    Operation::CompoundAssignment { target, ty, value } => {
+                        let t_type = target.get_type(scope)?;
+                        if binary_type(*ty, t_type, value.get_type(scope)?)? != t_type {
+                            return Err(TypeError::InvalidOperand {
+                                operator: ty.symbol(),
+                                ty: t_type,
+                            });
                         }
+                        Ok(PrimitiveTy::Void)
+                    }
+                    Operation::Cast { target, ty } => {
+                        let from = target.get_type(scope)?;
+                        let to = ty.resolve()?;
+                        if !can_cast(from, to) {
+                            return Err(TypeError::InvalidCast { from, to });
+                        }
+                        Ok(to)
                     }
                 }
             }
@@ -67,29 +403,167 @@ impl<'a> Expression<'a> {
     }
 }
 
+/// The type both operands of a binary operation are used as, letting an
+/// unsuffixed literal take the type of the other side.
+pub fn operand_type(l_type: PrimitiveTy, r_type: PrimitiveTy) -> Result<PrimitiveTy, TypeError> {
+    if r_type.coerces_to(l_type) {
+        Ok(l_type)
+    } else if l_type.coerces_to(r_type) {
+        Ok(r_type)
+    } else {
+        Err(TypeError::Mismatch {
+            expected: l_type,
+            found: r_type,
+        })
+    }
+}
+
+/// Whether `from as to` is allowed. Integers convert between each other, and
+/// `bool` and `char` convert to integers. Only `u8` converts to `char`, since
+/// every other integer type has values that aren't valid characters.
+fn can_cast(from: PrimitiveTy, to: PrimitiveTy) -> bool {
+    match (from, to) {
+        _ if from.coerces_to(to) => true,
+        (
+            PrimitiveTy::Number
+            | PrimitiveTy::Integer(_)
+            | PrimitiveTy::Boolean
+            | PrimitiveTy::Char,
+            PrimitiveTy::Integer(_),
+        ) => true,
+        (PrimitiveTy::Number | PrimitiveTy::Integer(IntegerTy::U8), PrimitiveTy::Char) => true,
+        _ => false,
+    }
+}
+
+/// Result type of applying `ty` to operands of type `l_type` and `r_type`.
+fn binary_type(
+    ty: BinaryOperationTy,
+    l_type: PrimitiveTy,
+    r_type: PrimitiveTy,
+) -> Result<PrimitiveTy, TypeError> {
+    let l_type = operand_type(l_type, r_type)?;
+    let invalid = || TypeError::InvalidOperand {
+        operator: ty.symbol(),
+        ty: l_type,
+    };
+    match ty {
+        BinaryOperationTy::Addition
+        | BinaryOperationTy::Subtraction
+        | BinaryOperationTy::Multiplication
+        | BinaryOperationTy::Division => match l_type {
+            ty if ty.is_integer() || ty.is_float() => Ok(l_type),
+            _ => Err(invalid()),
+        },
+        BinaryOperationTy::Remainder
+        | BinaryOperationTy::ShiftLeft
+        | BinaryOperationTy::ShiftRight => match l_type {
+            ty if ty.is_integer() => Ok(l_type),
+            _ => Err(invalid()),
+        },
+        BinaryOperationTy::BitAnd | BinaryOperationTy::BitOr | BinaryOperationTy::BitXor => {
+            match l_type {
+                ty if ty.is_integer() || ty == PrimitiveTy::Boolean => Ok(l_type),
+                _ => Err(invalid()),
+            }
+        }
+        BinaryOperationTy::Equal | BinaryOperationTy::NotEqual => match l_type {
+            PrimitiveTy::Void | PrimitiveTy::Str => Err(invalid()),
+            _ => Ok(PrimitiveTy::Boolean),
+        },
+        BinaryOperationTy::Less
+        | BinaryOperationTy::LessEqual
+        | BinaryOperationTy::Greater
+        | BinaryOperationTy::GreaterEqual => match l_type {
+            ty if ty.is_integer() || ty.is_float() || ty == PrimitiveTy::Char => {
+                Ok(PrimitiveTy::Boolean)
+            }
+            _ => Err(invalid()),
+        },
+        BinaryOperationTy::LogicalAnd | BinaryOperationTy::LogicalOr => match l_type {
+            PrimitiveTy::Boolean => Ok(PrimitiveTy::Boolean),
+            _ => Err(invalid()),
+        },
+        BinaryOperationTy::Assignment => Ok(PrimitiveTy::Void),
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ControlFlow<'a> {
     If {
         condition: Expression<'a>,
         body: Vec<Statement<'a>>,
+        /// `else if` is an `else` body holding just another `if`.
+        else_body: Option<Vec<Statement<'a>>>,
+    },
+    While {
+        label: Option<&'a str>,
+        condition: Expression<'a>,
+        body: Vec<Statement<'a>>,
     },
+    Loop {
+        label: Option<&'a str>,
+        body: Vec<Statement<'a>>,
+    },
+    /// `for variable in start..end`, with `inclusive` set for `..=`.
+    For {
+        label: Option<&'a str>,
+        variable: &'a str,
+        start: Expression<'a>,
+        end: Expression<'a>,
+        inclusive: bool,
+        body: Vec<Statement<'a>>,
+    },
+    Break(Option<&'a str>),
+    Continue(Option<&'a str>),
+}
+
+/// A type as written in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type<'a> {
+    /// A type referred to by name, such as `i32` or `bool`.
+    Named(&'a str),
+}
+
+impl<'a> Type<'a> {
+    pub fn resolve(&self) -> Result<PrimitiveTy, TypeError> {
+        match self {
+            Type::Named(name) => {
+                PrimitiveTy::from_name(name).ok_or_else(|| TypeError::UnknownType(name.to_string()))
+            }
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Statement<'a> {
     Declaration {
         name: &'a str,
+        /// The annotated type, inferred from the initializer when missing.
+        ty: Option<Type<'a>>,
         expression: Expression<'a>,
     },
     Expression(Expression<'a>),
     ControlFlow(ControlFlow<'a>),
+    Return(Option<Expression<'a>>),
+    /// A statement that failed to parse, the error is reported separately.
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Argument<'a> {
+    pub name: &'a str,
+    pub ty: Type<'a>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Function<'a> {
+    /// Text of the `##` doc comments before the function, one line each, without
+    /// the `##` and the space after it.
+    pub docs: Vec<&'a str>,
     pub name: &'a str,
-    pub arguments: Vec<()>,
-    pub return_type: Option<&'a str>,
+    pub arguments: Vec<Argument<'a>>,
+    pub return_type: Option<Type<'a>>,
     // Block type?
     // Block == Expression?
     pub body: Vec<Statement<'a>>,
@@ -100,11 +574,42 @@ pub struct Module<'a> {
     pub functions: Vec<Function<'a>>,
 }
 
+/// Something the parser was looking for when it found an unexpected token.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Token(TokenTy),
+    Expression,
+    Statement,
+    Type,
+}
+
+impl std::fmt::Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Token(ty) => write!(f, "{}", ty),
+            Expected::Expression => write!(f, "expression"),
+            Expected::Statement => write!(f, "statement"),
+            Expected::Type => write!(f, "type"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// Location of the offending token, empty at the end of the input.
+    pub section: TextSection,
+    /// The offending token, `None` at the end of the input.
+    pub found: Option<TokenTy>,
+    pub expected: Vec<Expected>,
+    pub message: String,
+}
+
 #[derive(Debug)]
 pub struct Parser<'a> {
     source: &'a str,
     tokens: &'a [Token],
     index: usize,
+    errors: Vec<ParseError>,
 }
 
 impl<'a> Parser<'a> {
@@ -113,212 +618,643 @@ impl<'a> Parser<'a> {
             source,
             tokens,
             index: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn parse(self) -> Result<Module<'a>, Vec<ParseError>> {
+        let (module, errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(module)
+        } else {
+            Err(errors)
         }
     }
-    pub fn parse(mut self) -> Module<'a> {
+
+    /// Parses as much of the input as possible, returning the partial [`Module`]
+    /// together with every error encountered along the way.
+    ///
+    /// Statements that fail to parse are replaced by [`Statement::Error`].
+    pub fn parse_recovering(mut self) -> (Module<'a>, Vec<ParseError>) {
         let mut module = Module {
             functions: Vec::new(),
         };
 
-        let Token { ty, .. } = self.read_token().unwrap();
-        match ty {
-            TokenTy::Keyword(KeywordTy::Function) => module.functions.push(self.parse_function()),
-            _ => panic!("Unexpected Token!"),
+        loop {
+            let docs = self.read_docs();
+            let token = match self.peek_token() {
+                Some(token) => token,
+                None => {
+                    // Doc comments have to document something.
+                    if !docs.is_empty() {
+                        let e = self.unexpected(
+                            None,
+                            vec![Expected::Token(TokenTy::Keyword(KeywordTy::Function))],
+                        );
+                        self.errors.push(e);
+                    }
+                    break;
+                }
+            };
+            match token.ty {
+                TokenTy::Keyword(KeywordTy::Function) => {
+                    self.read_token();
+                    match self.parse_function() {
+                        Ok(function) => module.functions.push(Function { docs, ..function }),
+                        Err(e) => {
+                            self.errors.push(e);
+                            self.skip_to_item();
+                        }
+                    }
+                }
+                _ => {
+                    let e = self.unexpected(
+                        Some(token),
+                        vec![Expected::Token(TokenTy::Keyword(KeywordTy::Function))],
+                    );
+                    self.errors.push(e);
+                    self.skip_to_item();
+                }
+            }
+        }
+        (module, self.errors)
+    }
+
+    /// Reads the doc comments before an item.
+    fn read_docs(&mut self) -> Vec<&'a str> {
+        let mut docs = Vec::new();
+        while let Some(token) = self.tokens.get(self.index) {
+            if token.ty != TokenTy::DocComment {
+                break;
+            }
+            let text = &self.source[token.range.start + 2..token.range.end];
+            docs.push(text.strip_prefix(' ').unwrap_or(text));
+            self.index += 1;
+        }
+        docs
+    }
+
+    /// Skips tokens up to the next `fn` or the doc comments before it.
+    fn skip_to_item(&mut self) {
+        while let Some(token) = self.tokens.get(self.index) {
+            if matches!(
+                token.ty,
+                TokenTy::Keyword(KeywordTy::Function) | TokenTy::DocComment
+            ) {
+                return;
+            }
+            self.index += 1;
+        }
+    }
+
+    /// Skips tokens until the end of the current statement: past the next `;` or
+    /// balanced `}`, or up to an unbalanced `}` or `fn`.
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek_token() {
+            match token.ty {
+                TokenTy::Keyword(KeywordTy::Function) => return,
+                TokenTy::Symbol(SymbolTy::ClBrace) if depth == 0 => return,
+                TokenTy::Symbol(SymbolTy::ClBrace) => {
+                    self.read_token();
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                TokenTy::Symbol(SymbolTy::OpBrace) => {
+                    self.read_token();
+                    depth += 1;
+                }
+                TokenTy::Symbol(SymbolTy::EndStmt) if depth == 0 => {
+                    self.read_token();
+                    return;
+                }
+                _ => {
+                    self.read_token();
+                }
+            }
         }
-        module
     }
 
     fn read_token(&mut self) -> Option<Token> {
-        let token = self.tokens.get(self.index)?.clone();
+        let token = self.peek_token()?;
         self.index += 1;
         Some(token)
     }
 
+    /// The next token, skipping doc comments that aren't in front of an item.
     fn peek_token(&mut self) -> Option<Token> {
+        while self.tokens.get(self.index)?.ty == TokenTy::DocComment {
+            self.index += 1;
+        }
         Some(self.tokens.get(self.index)?.clone())
     }
 
-    pub fn parse_expression(&mut self) -> Expression<'a> {
-        // TODO make this good
-        let expr = match self.read_token().unwrap() {
-            Token {
+    /// Builds the error for `found` (`None` at the end of the input) not being any of `expected`.
+    fn unexpected(&self, found: Option<Token>, expected: Vec<Expected>) -> ParseError {
+        let range = match &found {
+            Some(token) => token.range.clone(),
+            None => self.source.len()..self.source.len(),
+        };
+        let found_text = match &found {
+            Some(token) => format!("`{}`", &self.source[token.range.clone()]),
+            None => "end of input".to_owned(),
+        };
+        let message = format!(
+            "expected {}, found {}",
+            expected.iter().map(|e| e.to_string()).join(" or "),
+            found_text
+        );
+        ParseError {
+            section: TextSection::from_index(self.source, range),
+            found: found.map(|t| t.ty),
+            expected,
+            message,
+        }
+    }
+
+    fn expect(&mut self, ty: TokenTy) -> Result<Token, ParseError> {
+        match self.peek_token() {
+            Some(token) if token.ty == ty => {
+                self.read_token();
+                Ok(token)
+            }
+            t => Err(self.unexpected(t, vec![Expected::Token(ty)])),
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<&'a str, ParseError> {
+        let token = self.expect(TokenTy::Identifier)?;
+        Ok(&self.source[token.range])
+    }
+
+    pub fn parse_expression(&mut self) -> Result<Expression<'a>, ParseError> {
+        self.parse_binary(0)
+    }
+
+    /// Precedence climbing: parses operands joined by binary operators whose left
+    /// binding power is at least `min_power`.
+    fn parse_binary(&mut self, min_power: u8) -> Result<Expression<'a>, ParseError> {
+        let mut left = self.parse_primary()?;
+        while let Some(token) = self.peek_token() {
+            if let Some(ty) = BinaryOperationTy::from_token(token.ty) {
+                let (left_power, right_power) = ty.binding_power();
+                if left_power < min_power {
+                    break;
+                }
+                self.read_token();
+                let right = self.parse_binary(right_power)?;
+                left = Expression::Operation(Rc::new(Operation::Binary { left, ty, right }));
+            } else if let Some(ty) = BinaryOperationTy::from_compound_token(token.ty) {
+                // Compound assignments bind like `=`.
+                let (left_power, right_power) = BinaryOperationTy::Assignment.binding_power();
+                if left_power < min_power {
+                    break;
+                }
+                self.read_token();
+                let value = self.parse_binary(right_power)?;
+                left = Expression::Operation(Rc::new(Operation::CompoundAssignment {
+                    target: left,
+                    ty,
+                    value,
+                }));
+            } else if token.ty == TokenTy::Keyword(KeywordTy::As) {
+                if CAST_BINDING_POWER < min_power {
+                    break;
+                }
+                self.read_token();
+                let ty = self.parse_type()?;
+                left = Expression::Operation(Rc::new(Operation::Cast { target: left, ty }));
+            } else {
+                break;
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression<'a>, ParseError> {
+        if let Some(ty) = self.peek_token().and_then(|t| UnaryOperationTy::from_token(t.ty)) {
+            self.read_token();
+            let target = self.parse_binary(ty.binding_power())?;
+            return Ok(Expression::Operation(Rc::new(Operation::Unary { target, ty })));
+        }
+        match self.peek_token() {
+            Some(Token {
                 ty: TokenTy::Literal(ty),
                 range,
-            } => Expression::Literal(Literal {
-                value: &self.source[range],
-                ty,
-            }),
-            Token {
+            }) => {
+                self.read_token();
+                let value = &self.source[range];
+                Ok(Expression::Literal(Literal {
+                    value,
+                    ty,
+                    parsed: ParsedLiteral::new(value, ty),
+                }))
+            }
+            Some(Token {
                 ty: TokenTy::Identifier,
                 range,
-            } => Expression::Variable(&self.source[range]),
-            _ => panic!("Unexpected Token!"),
-        };
-        let next_expr = self.peek_token();
-        match next_expr {
+            }) => {
+                self.read_token();
+                let name = &self.source[range];
+                match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::OpParen),
+                        ..
+                    }) => {
+                        self.read_token();
+                        let arguments = self.parse_list(|parser| parser.parse_expression())?;
+                        Ok(Expression::Call(Call { name, arguments }))
+                    }
+                    _ => Ok(Expression::Variable(name)),
+                }
+            }
             Some(Token {
-                ty: TokenTy::Symbol(op @ (SymbolTy::Add | SymbolTy::Greater)),
+                ty: TokenTy::Symbol(SymbolTy::OpParen),
                 ..
             }) => {
                 self.read_token();
-                let right = self.parse_expression();
-                Expression::Operation(Rc::new(Operation::Binary {
-                    left: expr,
-                    ty: match op {
-                        SymbolTy::Add => BinaryOperationTy::Addition,
-                        SymbolTy::Greater => BinaryOperationTy::Greater,
-                        _ => panic!("Unexpected Operation!"),
-                    },
-                    right,
-                }))
+                let expr = self.parse_expression()?;
+                self.expect(TokenTy::Symbol(SymbolTy::ClParen))?;
+                Ok(expr)
             }
-            None
-            | Some(Token {
-                ty: TokenTy::Symbol(SymbolTy::EndStmt | SymbolTy::OpBrace),
-                ..
-            }) => expr,
-            t => panic!("Unexpected Token! {:?}", t),
+            t => Err(self.unexpected(t, vec![Expected::Expression])),
         }
     }
 
+    /// Parses statements up to and including the closing `}`, recovering from
+    /// errors at statement boundaries.
     pub fn parse_body(&mut self) -> Vec<Statement<'a>> {
         let mut body = Vec::new();
         loop {
-            let statement = match self.read_token().unwrap() {
-                Token {
-                    ty: TokenTy::Keyword(KeywordTy::Let),
-                    ..
-                } => {
-                    let name = match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Identifier,
-                            range,
-                        } => &self.source[range],
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Symbol(SymbolTy::Assign),
-                            ..
-                        } => {}
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    let expression = self.parse_expression();
-                    match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Symbol(SymbolTy::EndStmt),
-                            ..
-                        } => {}
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    Statement::Declaration { name, expression }
-                }
-                Token {
-                    ty: TokenTy::Keyword(KeywordTy::If),
-                    ..
-                } => {
-                    let condition = self.parse_expression();
-                    match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Symbol(SymbolTy::OpBrace),
-                            ..
-                        } => {}
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    let body = self.parse_body();
-                    Statement::ControlFlow(ControlFlow::If { condition, body })
-                }
-                Token {
-                    ty: TokenTy::Identifier,
-                    range,
-                } => {
-                    match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Symbol(SymbolTy::Assign),
-                            ..
-                        } => {}
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    let expr = self.parse_expression();
-                    match self.read_token().unwrap() {
-                        Token {
-                            ty: TokenTy::Symbol(SymbolTy::EndStmt),
-                            ..
-                        } => {}
-                        _ => panic!("Unexpected Token!"),
-                    };
-                    Statement::Expression(Expression::Operation(Rc::new(Operation::Binary {
-                        left: Expression::Variable(&self.source[range]),
-                        ty: BinaryOperationTy::Assignment,
-                        right: expr,
-                    })))
+            match self.parse_statement() {
+                Ok(Some(statement)) => body.push(statement),
+                Ok(None) => break,
+                Err(e) => {
+                    // Unclosed nested bodies all fail at the same token, only report it once.
+                    if self.errors.last().map(|last| &last.section) != Some(&e.section) {
+                        self.errors.push(e);
+                    }
+                    self.synchronize();
+                    body.push(Statement::Error);
+                    if let None
+                    | Some(Token {
+                        ty: TokenTy::Keyword(KeywordTy::Function),
+                        ..
+                    }) = self.peek_token()
+                    {
+                        break;
+                    }
                 }
-                Token {
-                    ty: TokenTy::Symbol(SymbolTy::ClBrace),
-                    ..
-                } => break,
-                t => panic!("Unexpected Token! {:?}", t),
-            };
-            body.push(statement);
+            }
         }
         body
     }
 
-    pub fn parse_function(&mut self) -> Function<'a> {
-        let name = match self.read_token().unwrap() {
-            Token {
+    /// Parses a single statement, returning `None` after the `}` ending the body.
+    fn parse_statement(&mut self) -> Result<Option<Statement<'a>>, ParseError> {
+        let statement = match self.peek_token() {
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::Let),
+                ..
+            }) => {
+                self.read_token();
+                let name = self.expect_identifier()?;
+                let ty = match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::Colon),
+                        ..
+                    }) => {
+                        self.read_token();
+                        Some(self.parse_type()?)
+                    }
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::Assign),
+                        ..
+                    }) => None,
+                    t => {
+                        return Err(self.unexpected(
+                            t,
+                            vec![
+                                Expected::Token(TokenTy::Symbol(SymbolTy::Colon)),
+                                Expected::Token(TokenTy::Symbol(SymbolTy::Assign)),
+                            ],
+                        ))
+                    }
+                };
+                self.expect(TokenTy::Symbol(SymbolTy::Assign))?;
+                let expression = self.parse_expression()?;
+                self.expect(TokenTy::Symbol(SymbolTy::EndStmt))?;
+                Statement::Declaration {
+                    name,
+                    ty,
+                    expression,
+                }
+            }
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::Return),
+                ..
+            }) => {
+                self.read_token();
+                let value = match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::EndStmt),
+                        ..
+                    }) => None,
+                    _ => Some(self.parse_expression()?),
+                };
+                self.expect(TokenTy::Symbol(SymbolTy::EndStmt))?;
+                Statement::Return(value)
+            }
+            Some(Token {
+                ty: TokenTy::Label,
                 range,
-                ty: TokenTy::Identifier,
-            } => &self.source[range],
-            _ => panic!("Unexpected Token!"),
+            }) => {
+                self.read_token();
+                self.expect(TokenTy::Symbol(SymbolTy::Colon))?;
+                let label = &self.source[range.start + 1..range.end];
+                Statement::ControlFlow(self.parse_loop(Some(label))?)
+            }
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::While | KeywordTy::Loop | KeywordTy::For),
+                ..
+            }) => Statement::ControlFlow(self.parse_loop(None)?),
+            Some(Token {
+                ty: TokenTy::Keyword(keyword @ (KeywordTy::Break | KeywordTy::Continue)),
+                ..
+            }) => {
+                self.read_token();
+                let label = match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Label,
+                        range,
+                    }) => {
+                        self.read_token();
+                        Some(&self.source[range.start + 1..range.end])
+                    }
+                    _ => None,
+                };
+                self.expect(TokenTy::Symbol(SymbolTy::EndStmt))?;
+                Statement::ControlFlow(match keyword {
+                    KeywordTy::Break => ControlFlow::Break(label),
+                    _ => ControlFlow::Continue(label),
+                })
+            }
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::If),
+                ..
+            }) => {
+                self.read_token();
+                Statement::ControlFlow(self.parse_if()?)
+            }
+            Some(Token {
+                ty:
+                    TokenTy::Identifier
+                    | TokenTy::Literal(_)
+                    | TokenTy::Symbol(SymbolTy::OpParen | SymbolTy::Sub | SymbolTy::Not),
+                ..
+            }) => {
+                let expr = self.parse_expression()?;
+                self.expect(TokenTy::Symbol(SymbolTy::EndStmt))?;
+                Statement::Expression(expr)
+            }
+            Some(Token {
+                ty: TokenTy::Symbol(SymbolTy::ClBrace),
+                ..
+            }) => {
+                self.read_token();
+                return Ok(None);
+            }
+            t => {
+                return Err(self.unexpected(
+                    t,
+                    vec![
+                        Expected::Statement,
+                        Expected::Token(TokenTy::Symbol(SymbolTy::ClBrace)),
+                    ],
+                ))
+            }
+        };
+        Ok(Some(statement))
+    }
+
+    /// Parses a `while`, `loop` or `for` statement, starting at the keyword.
+    fn parse_loop(&mut self, label: Option<&'a str>) -> Result<ControlFlow<'a>, ParseError> {
+        match self.peek_token() {
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::While),
+                ..
+            }) => {
+                self.read_token();
+                let condition = self.parse_expression()?;
+                self.expect(TokenTy::Symbol(SymbolTy::OpBrace))?;
+                let body = self.parse_body();
+                Ok(ControlFlow::While {
+                    label,
+                    condition,
+                    body,
+                })
+            }
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::Loop),
+                ..
+            }) => {
+                self.read_token();
+                self.expect(TokenTy::Symbol(SymbolTy::OpBrace))?;
+                let body = self.parse_body();
+                Ok(ControlFlow::Loop { label, body })
+            }
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::For),
+                ..
+            }) => {
+                self.read_token();
+                let variable = self.expect_identifier()?;
+                self.expect(TokenTy::Keyword(KeywordTy::In))?;
+                let start = self.parse_expression()?;
+                let inclusive = match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::Range),
+                        ..
+                    }) => false,
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::RangeInclusive),
+                        ..
+                    }) => true,
+                    t => {
+                        return Err(self.unexpected(
+                            t,
+                            vec![
+                                Expected::Token(TokenTy::Symbol(SymbolTy::Range)),
+                                Expected::Token(TokenTy::Symbol(SymbolTy::RangeInclusive)),
+                            ],
+                        ))
+                    }
+                };
+                self.read_token();
+                let end = self.parse_expression()?;
+                self.expect(TokenTy::Symbol(SymbolTy::OpBrace))?;
+                let body = self.parse_body();
+                Ok(ControlFlow::For {
+                    label,
+                    variable,
+                    start,
+                    end,
+                    inclusive,
+                    body,
+                })
+            }
+            t => Err(self.unexpected(
+                t,
+                vec![
+                    Expected::Token(TokenTy::Keyword(KeywordTy::While)),
+                    Expected::Token(TokenTy::Keyword(KeywordTy::Loop)),
+                    Expected::Token(TokenTy::Keyword(KeywordTy::For)),
+                ],
+            )),
+        }
+    }
+
+    /// Parses the rest of an `if` chain after the `if` keyword.
+    fn parse_if(&mut self) -> Result<ControlFlow<'a>, ParseError> {
+        let condition = self.parse_expression()?;
+        self.expect(TokenTy::Symbol(SymbolTy::OpBrace))?;
+        let body = self.parse_body();
+        let else_body = match self.peek_token() {
+            Some(Token {
+                ty: TokenTy::Keyword(KeywordTy::Else),
+                ..
+            }) => {
+                self.read_token();
+                match self.peek_token() {
+                    Some(Token {
+                        ty: TokenTy::Keyword(KeywordTy::If),
+                        ..
+                    }) => {
+                        self.read_token();
+                        Some(vec![Statement::ControlFlow(self.parse_if()?)])
+                    }
+                    Some(Token {
+                        ty: TokenTy::Symbol(SymbolTy::OpBrace),
+                        ..
+                    }) => {
+                        self.read_token();
+                        Some(self.parse_body())
+                    }
+                    t => {
+                        return Err(self.unexpected(
+                            t,
+                            vec![
+                                Expected::Token(TokenTy::Keyword(KeywordTy::If)),
+                                Expected::Token(TokenTy::Symbol(SymbolTy::OpBrace)),
+                            ],
+                        ))
+                    }
+                }
+            }
+            _ => None,
         };
-        let arguments = match (self.read_token().unwrap(), self.read_token().unwrap()) {
-            (
-                Token {
-                    ty: TokenTy::Symbol(SymbolTy::OpParen),
+        Ok(ControlFlow::If {
+            condition,
+            body,
+            else_body,
+        })
+    }
+
+    /// Parses comma separated items up to and including the closing `)`, allowing a
+    /// trailing comma.
+    fn parse_list<T>(
+        &mut self,
+        mut parse_item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(Token {
+                ty: TokenTy::Symbol(SymbolTy::ClParen),
+                ..
+            }) = self.peek_token()
+            {
+                self.read_token();
+                return Ok(items);
+            }
+            items.push(parse_item(self)?);
+            match self.peek_token() {
+                Some(Token {
+                    ty: TokenTy::Symbol(SymbolTy::Comma),
                     ..
-                },
-                Token {
+                }) => {
+                    self.read_token();
+                }
+                Some(Token {
                     ty: TokenTy::Symbol(SymbolTy::ClParen),
                     ..
-                },
-            ) => Vec::new(),
-            _ => panic!("Unexpected Token!"),
-        };
-        let return_type = match self.peek_token().unwrap() {
-            Token {
+                }) => {}
+                t => {
+                    return Err(self.unexpected(
+                        t,
+                        vec![
+                            Expected::Token(TokenTy::Symbol(SymbolTy::Comma)),
+                            Expected::Token(TokenTy::Symbol(SymbolTy::ClParen)),
+                        ],
+                    ))
+                }
+            }
+        }
+    }
+
+    pub fn parse_type(&mut self) -> Result<Type<'a>, ParseError> {
+        match self.peek_token() {
+            Some(Token {
+                ty: TokenTy::Identifier,
+                range,
+            }) => {
+                self.read_token();
+                Ok(Type::Named(&self.source[range]))
+            }
+            t => Err(self.unexpected(t, vec![Expected::Type])),
+        }
+    }
+
+    pub fn parse_function(&mut self) -> Result<Function<'a>, ParseError> {
+        let name = self.expect_identifier()?;
+        self.expect(TokenTy::Symbol(SymbolTy::OpParen))?;
+        let arguments = self.parse_list(|parser| {
+            let name = parser.expect_identifier()?;
+            parser.expect(TokenTy::Symbol(SymbolTy::Colon))?;
+            let ty = parser.parse_type()?;
+            Ok(Argument { name, ty })
+        })?;
+        let return_type = match self.peek_token() {
+            Some(Token {
                 ty: TokenTy::Symbol(SymbolTy::ThinArrow),
                 ..
-            } => {
+            }) => {
                 self.read_token();
-                let type_name = match self.read_token().unwrap() {
-                    Token {
-                        range,
-                        ty: TokenTy::Identifier,
-                    } => &self.source[range],
-                    _ => panic!("Unexpected Token!"),
-                };
-                Some(type_name)
+                Some(self.parse_type()?)
             }
-            Token {
+            Some(Token {
                 ty: TokenTy::Symbol(SymbolTy::OpBrace),
                 ..
-            } => None,
-            _ => panic!("Unexpected Token!"),
+            }) => None,
+            t => {
+                return Err(self.unexpected(
+                    t,
+                    vec![
+                        Expected::Token(TokenTy::Symbol(SymbolTy::ThinArrow)),
+                        Expected::Token(TokenTy::Symbol(SymbolTy::OpBrace)),
+                    ],
+                ))
+            }
         };
-        match self.read_token().unwrap() {
-            Token {
-                ty: TokenTy::Symbol(SymbolTy::OpBrace),
-                ..
-            } => {}
-            _ => panic!("Unexpected Token!"),
-        }
+        self.expect(TokenTy::Symbol(SymbolTy::OpBrace))?;
         let body = self.parse_body();
-        Function {
+        Ok(Function {
+            docs: Vec::new(),
             name,
             arguments,
             return_type,
             body,
-        }
+        })
     }
 }
@@ -0,0 +1,831 @@
+use std::process::Command;
+
+use deltac::{
+    codegen::{compile_module, CodegenError},
+    lexer::tokenizer::Tokenizer,
+    parse::{Parser, TypeError},
+    FloatTy, IntegerTy, PrimitiveTy,
+};
+
+fn compile(input: &str) -> Result<String, CodegenError> {
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    compile_module(&module)
+}
+
+/// Checks that gcc accepts the generated assembly.
+fn assemble(name: &str, asm: &str) {
+    let dir = std::env::temp_dir();
+    let asm_path = dir.join(format!("deltac-test-{}-{}.s", name, std::process::id()));
+    let obj_path = asm_path.with_extension("o");
+    std::fs::write(&asm_path, asm).unwrap();
+    let status = Command::new("gcc")
+        .arg("-c")
+        .arg(&asm_path)
+        .arg("-o")
+        .arg(&obj_path)
+        .status()
+        .unwrap();
+    let _ = std::fs::remove_file(&asm_path);
+    let _ = std::fs::remove_file(&obj_path);
+    assert!(status.success(), "gcc rejected:\n{}", asm);
+}
+
+/// Builds an executable from `input` and returns its exit code.
+fn run(name: &str, input: &str) -> i32 {
+    let asm = compile(input).unwrap();
+    let dir = std::env::temp_dir();
+    let asm_path = dir.join(format!("deltac-run-{}-{}.s", name, std::process::id()));
+    let exe_path = asm_path.with_extension("");
+    std::fs::write(&asm_path, &asm).unwrap();
+    let status = Command::new("gcc")
+        .arg("-no-pie")
+        .arg(&asm_path)
+        .arg("-o")
+        .arg(&exe_path)
+        .status()
+        .unwrap();
+    let _ = std::fs::remove_file(&asm_path);
+    assert!(status.success(), "gcc rejected:\n{}", asm);
+    let status = Command::new(&exe_path).status().unwrap();
+    let _ = std::fs::remove_file(&exe_path);
+    status.code().unwrap()
+}
+
+#[test]
+fn compile_if() {
+    let asm = compile(
+        "
+fn main() {
+    let a = 10;
+    if a > 5 {
+        a = 5;
+    }
+}",
+    )
+    .unwrap();
+    assert!(asm.contains("main:\n"));
+    assert!(asm.contains("movl $10, -4(%rbp)"));
+    assert!(asm.contains("jle .Lmain_0"));
+}
+
+#[test]
+fn unknown_variable() {
+    assert_eq!(
+        compile("fn main() { a = 5; }"),
+        Err(CodegenError::Type(TypeError::UnknownVariable("a".to_owned())))
+    );
+}
+
+#[test]
+fn compile_multiple_functions() {
+    let asm = compile("fn helper() { let b = 1; } fn main() { let a = 2; }").unwrap();
+    assert!(asm.contains("deltafunc_helper:\n"));
+    assert!(asm.contains("main:\n"));
+}
+
+#[test]
+fn compile_operators() {
+    let asm = compile(
+        "
+fn main() {
+    let a = 10;
+    let b = a * 3;
+    let c = b / a;
+    let d = b % 7;
+    let e = -a;
+    let f = a << 2;
+    let g = a ^ 5;
+    let h = a <= 2;
+    let i = !h;
+    let j = h || i;
+    if h != i {
+        a = 1;
+    }
+    if j {
+        a = 2;
+    }
+    if a == 10 {
+        a = 3;
+    }
+}",
+    )
+    .unwrap();
+    assert!(asm.contains("imull $3, %eax"));
+    assert!(asm.contains("idivl %ecx"));
+    assert!(asm.contains("setle %al"));
+    assert!(asm.contains("jne .Lmain_3"));
+    assemble("operators", &asm);
+}
+
+#[test]
+fn invalid_operand() {
+    assert_eq!(
+        compile("fn main() { let a = 1 > 2; let b = a + a; }"),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "+",
+            ty: PrimitiveTy::Boolean
+        }))
+    );
+}
+
+#[test]
+fn compile_nested_expressions() {
+    let asm = compile(
+        "
+fn main() {
+    let a = 10;
+    let b = a + 1;
+    let c = (a + 1) * (b - 2) / (a % 3 + 1);
+    let d = c > a + b && !(a == b) || a < 0;
+    if (a + b) * 2 > c - 1 {
+        a = -(b << 1);
+    }
+}",
+    )
+    .unwrap();
+    assert!(asm.contains("push %rax"));
+    assert!(asm.contains("pop %rcx"));
+    assemble("nested", &asm);
+}
+
+#[test]
+fn compile_calls() {
+    let asm = compile(
+        "
+fn add(a: i32, b: i32) -> i32 {
+    let c = a + b;
+    return c;
+}
+fn check(x: bool) {}
+fn main() {
+    let a = add(1, add(2, 3)) * 2;
+    check(a > 4);
+}",
+    )
+    .unwrap();
+    assert!(asm.contains("movl %edi, -4(%rbp)"));
+    assert!(asm.contains("movl %esi, -8(%rbp)"));
+    assert!(asm.contains("movb %dil, -1(%rbp)"));
+    assert!(asm.contains("call deltafunc_add"));
+    assemble("calls", &asm);
+}
+
+#[test]
+fn call_arity() {
+    assert_eq!(
+        compile("fn f(a: i32) {} fn main() { f(1, 2); }"),
+        Err(CodegenError::Type(TypeError::ArgumentCount {
+            function: "f".to_owned(),
+            expected: 1,
+            found: 2
+        }))
+    );
+    assert_eq!(
+        compile("fn f(a: bool) {} fn main() { f(1); }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Boolean,
+            found: PrimitiveTy::Number
+        }))
+    );
+}
+
+#[test]
+fn run_return() {
+    assert_eq!(run("return_void", "fn main() { let a = 1; }"), 0);
+    assert_eq!(
+        run(
+            "return_early",
+            "fn main() { let a = 7; a = a * 37; if a > 0 { return; } }"
+        ),
+        0
+    );
+    assert_eq!(
+        run(
+            "return_value",
+            "
+fn add(a: i32, b: i32) -> i32 {
+    return a + b;
+}
+fn main() -> i32 {
+    let a = add(1, add(2, 3)) * 2;
+    if a > 100 {
+        return 1;
+    }
+    return a - (10 - 7) % 2 + -a / 4;
+}",
+        ),
+        8
+    );
+}
+
+#[test]
+fn run_stack_arguments() {
+    let input = "
+fn ints(a: i32, b: i64, c: i8, d: i32, e: i32, f: i32, g: i16, h: i64) -> i64 {
+    return a as i64 - b + (c as i32 * d - e + f * g as i32) as i64 - h;
+}
+fn floats(a: f64, b: f32, c: f64, d: f64, e: f64, f: f64, g: f64, h: f64, i: f32, j: f64) -> f64 {
+    if b != 2.0 || i != 9.0 {
+        return 0.0;
+    }
+    return a + c + d + e + f + g + h + j * 1000.0;
+}
+fn mixed(x: f64, a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, g: i32, y: f32) -> i32 {
+    if x != 1.5 || y != 2.5 {
+        return 0;
+    }
+    return g - f + e - d + c - b + a;
+}
+fn main() -> i32 {
+    if ints(1, 2, 3, 4, 5, 6, 7, 8) != 40 {
+        return 1;
+    }
+    if floats(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0) != 10034.0 {
+        return 2;
+    }
+    if 1 + mixed(1.5, 1, 2, 3, 4, 5, 6, 7, 2.5) != 5 {
+        return 3;
+    }
+    let a = ints(1, 0, 0, 0, 0, 0, 0, ints(0, 0, 0, 0, 0, 0, 0, -9));
+    if a != -8 {
+        return 4;
+    }
+    return 0;
+}";
+    assert!(compile(input).unwrap().contains("16(%rbp)"));
+    assert_eq!(run("stack_arguments", input), 0);
+}
+
+#[test]
+fn return_type_errors() {
+    assert_eq!(
+        compile("fn f() -> i32 { return 1 > 2; } fn main() {}"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Integer(IntegerTy::I32),
+            found: PrimitiveTy::Boolean
+        }))
+    );
+    assert_eq!(
+        compile("fn f() -> i32 { let a = 1; } fn main() {}"),
+        Err(CodegenError::MissingReturn("f".to_owned()))
+    );
+    assert_eq!(
+        compile("fn main() { return 1; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Void,
+            found: PrimitiveTy::Number
+        }))
+    );
+}
+
+#[test]
+fn run_else_if() {
+    let input = "
+fn classify(a: i32) -> i32 {
+    if a < 0 {
+        return 1;
+    } else if a == 0 {
+        return 2;
+    } else {
+        let b = a * 2;
+        if b > 10 {
+            b = 10;
+        } else {
+            b = b + 1;
+        }
+        return b;
+    }
+}
+fn main() -> i32 {
+    return classify(-5) * 100 + classify(0) * 10 + classify(2) + classify(9);
+}";
+    assert_eq!(run("else_if", input), 135);
+}
+
+#[test]
+fn run_loops() {
+    let input = "
+fn first() -> i32 {
+    loop {
+        return 4;
+    }
+}
+fn main() -> i32 {
+    let sum = 0;
+    let i = 0;
+    while i < 10 {
+        i = i + 1;
+        if i % 2 == 0 {
+            continue;
+        }
+        sum = sum + i;
+    }
+    let n = 0;
+    'outer: loop {
+        let j = 0;
+        loop {
+            j = j + 1;
+            n = n + 1;
+            if j == 3 {
+                continue 'outer;
+            }
+            if n > 10 {
+                break 'outer;
+            }
+        }
+    }
+    return sum + n + first();
+}";
+    assert_eq!(run("loops", input), 40);
+}
+
+#[test]
+fn loop_control_errors() {
+    assert_eq!(
+        compile("fn main() { break; }"),
+        Err(CodegenError::OutsideLoop("break"))
+    );
+    assert_eq!(
+        compile("fn main() { if 1 > 0 { continue; } }"),
+        Err(CodegenError::OutsideLoop("continue"))
+    );
+    assert_eq!(
+        compile("fn main() { 'a: loop { break 'b; } }"),
+        Err(CodegenError::UnknownLabel("b".to_owned()))
+    );
+    assert_eq!(
+        compile("fn f() -> i32 { loop { break; } } fn main() {}"),
+        Err(CodegenError::MissingReturn("f".to_owned()))
+    );
+}
+
+#[test]
+fn run_for() {
+    let input = "
+fn main() -> i32 {
+    let total = 0;
+    for i in 0..5 {
+        total = total + i;
+    }
+    for i in 1..=3 {
+        i = i * 10;
+        total = total + i;
+    }
+    let n = 4;
+    'outer: for i in 0..n {
+        for j in 0..10 {
+            if j > i {
+                continue 'outer;
+            }
+            if i == 3 {
+                break 'outer;
+            }
+            total = total + 1;
+        }
+    }
+    let x = 1;
+    if x == 1 {
+        let x = 2;
+        total = total + x;
+    }
+    return total + x;
+}";
+    assert_eq!(run("for", input), 79);
+}
+
+#[test]
+fn scoped_variables() {
+    assert_eq!(
+        compile("fn main() { for i in 0..3 {} let a = i; }"),
+        Err(CodegenError::Type(TypeError::UnknownVariable("i".to_owned())))
+    );
+    assert_eq!(
+        compile("fn main() { if 1 > 0 { let a = 1; } a = 2; }"),
+        Err(CodegenError::Type(TypeError::UnknownVariable("a".to_owned())))
+    );
+}
+
+#[test]
+fn run_booleans() {
+    let input = "
+fn not(b: bool) -> bool {
+    return b == false;
+}
+fn main() -> i32 {
+    let done = false;
+    let count = 0;
+    while !done {
+        count = count + 1;
+        if count == 3 {
+            done = true;
+        }
+    }
+    let flag = true && not(false);
+    if flag != true {
+        return 100;
+    }
+    if true {
+        count = count + 10;
+    }
+    return count;
+}";
+    assert_eq!(run("booleans", input), 13);
+}
+
+#[test]
+fn run_compound_assignment() {
+    let input = "
+fn main() -> i32 {
+    let a = 5;
+    a += 10;
+    a -= 3;
+    a *= a - 8;
+    a /= 5;
+    a %= 7;
+    let b = 1;
+    b <<= a;
+    b |= 1;
+    b ^= 4;
+    b &= 15;
+    b >>= 1;
+    let c = true;
+    c &= a > 100;
+    if c {
+        return 100;
+    }
+    return a * 10 + b;
+}";
+    assert_eq!(run("compound_assignment", input), 20);
+}
+
+#[test]
+fn compound_assignment_errors() {
+    let asm = compile("fn main() { let a = 1; a += 5; }").unwrap();
+    assert!(asm.contains("addl $5, -4(%rbp)"));
+    assert_eq!(
+        compile("fn main() { let a = true; a += 1; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Boolean,
+            found: PrimitiveTy::Number
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a = true; a -= false; }"),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "-",
+            ty: PrimitiveTy::Boolean
+        }))
+    );
+}
+
+#[test]
+fn declaration_annotations() {
+    assert!(compile("fn main() { let a: i32 = 1; let b: bool = a > 0; }").is_ok());
+    assert_eq!(
+        compile("fn main() { let a: bool = 1; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Boolean,
+            found: PrimitiveTy::Number
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a: text = 1; }"),
+        Err(CodegenError::Type(TypeError::UnknownType("text".to_owned())))
+    );
+}
+
+#[test]
+fn run_sized_integers() {
+    let input = "
+fn double(v: u16) -> u16 {
+    return v * 2;
+}
+fn main() -> i32 {
+    let a: u8 = 250;
+    a += 10;
+    if a != 4 {
+        return 1;
+    }
+    let b: i8 = 100;
+    b += 100;
+    if b != -56 {
+        return 2;
+    }
+    if b > 0 {
+        return 3;
+    }
+    let big: u32 = 4000000000;
+    if big < 5 {
+        return 4;
+    }
+    if big / 2 != 2000000000 {
+        return 5;
+    }
+    let x: i64 = 5000000000;
+    x = x * 3;
+    if x != 15000000000 {
+        return 6;
+    }
+    let y = 1u64 << 40;
+    y >>= 38;
+    if y != 4 {
+        return 7;
+    }
+    let s: i16 = -300;
+    s >>= 2;
+    if s != -75 {
+        return 8;
+    }
+    let u: u16 = 65535;
+    u >>= 4;
+    if u != 4095 {
+        return 9;
+    }
+    if double(200) != 400 {
+        return 10;
+    }
+    let m: i8 = -128;
+    if m + 1 != -127 {
+        return 11;
+    }
+    let total: usize = 0;
+    for i in 0usize..10 {
+        total += i;
+    }
+    if total != 45 {
+        return 12;
+    }
+    let n: u8 = 255;
+    for k in 250u8..=n {
+        total += 1;
+    }
+    if total != 51 {
+        return 13;
+    }
+    return 0;
+}";
+    assert_eq!(run("sized_integers", input), 0);
+}
+
+#[test]
+fn sized_integer_errors() {
+    let asm = compile("fn main() { let a: u8 = 1; let b: i16 = 2; let c: i64 = 3; }").unwrap();
+    assert!(asm.contains("movb $1, -1(%rbp)"));
+    assert!(asm.contains("movw $2, -4(%rbp)"));
+    assert!(asm.contains("movq $3, -16(%rbp)"));
+    assert_eq!(
+        compile("fn main() { let a: u8 = 256; }"),
+        Err(CodegenError::LiteralOutOfRange {
+            value: "256".to_owned(),
+            ty: PrimitiveTy::Integer(IntegerTy::U8)
+        })
+    );
+    assert_eq!(
+        compile("fn main() { let a: i8 = -129; }"),
+        Err(CodegenError::LiteralOutOfRange {
+            value: "-129".to_owned(),
+            ty: PrimitiveTy::Integer(IntegerTy::I8)
+        })
+    );
+    assert_eq!(
+        compile("fn main() { let a: u8 = 1; let b: i32 = a; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Integer(IntegerTy::I32),
+            found: PrimitiveTy::Integer(IntegerTy::U8)
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a = 1u32; let b = -a; }"),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "-",
+            ty: PrimitiveTy::Integer(IntegerTy::U32)
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a: u32 = -(2 * 3); }"),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "-",
+            ty: PrimitiveTy::Integer(IntegerTy::U32)
+        }))
+    );
+}
+
+#[test]
+fn run_floats() {
+    let input = "
+fn mix(a: i32, x: f64, b: i64, y: f32) -> f64 {
+    return x * 2.0 - 1.0 + 0.5;
+}
+fn half(v: f32) -> f32 {
+    return v / 2.0;
+}
+fn main() -> i32 {
+    let a = 1.5;
+    let b: f64 = 2.25e1;
+    if a + b != 24.0 {
+        return 1;
+    }
+    if b / a != 15.0 {
+        return 2;
+    }
+    let c = 0.1f32;
+    c *= 10.0;
+    if c != 1.0 {
+        return 3;
+    }
+    if !(a < b) || a > b || !(a <= 1.5) || !(b >= 22.5) {
+        return 4;
+    }
+    let n = -a;
+    if n != -1.5 || -n != a {
+        return 5;
+    }
+    if mix(1, 3.0, 2, 4.0) != 5.5 {
+        return 6;
+    }
+    if half(half(10.0)) != 2.5 {
+        return 7;
+    }
+    let zero = 0.0;
+    let nan = zero / zero;
+    if nan == nan || !(nan != nan) || nan < 1.0 || nan >= 1.0 {
+        return 8;
+    }
+    let big = 1e300;
+    if big * 10.0 <= big {
+        return 9;
+    }
+    let d = (1.5 + 1.0) as f32;
+    if d != 2.5f32 || 1.5 as f32 != 1.5f32 {
+        return 10;
+    }
+    return 0;
+}";
+    assert_eq!(run("floats", input), 0);
+}
+
+#[test]
+fn float_errors() {
+    let asm = compile("fn main() { let a: f32 = 1.5; let b = 2.0; }").unwrap();
+    assert!(asm.contains("movl $1069547520, -4(%rbp)"));
+    assert!(asm.contains("movabsq $4611686018427387904, %rax\nmovq %rax, -16(%rbp)"));
+    assert_eq!(
+        compile("fn main() { let a = 1.5 % 2.0; }"),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "%",
+            ty: PrimitiveTy::Decimal
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a = 1.5f32; let b: f64 = a; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Float(FloatTy::F64),
+            found: PrimitiveTy::Float(FloatTy::F32)
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a: f32 = 1e39; }"),
+        Err(CodegenError::LiteralOutOfRange {
+            value: "1e39".to_owned(),
+            ty: PrimitiveTy::Float(FloatTy::F32)
+        })
+    );
+    assert_eq!(
+        compile("fn main() { let a: f64 = 1; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Float(FloatTy::F64),
+            found: PrimitiveTy::Number
+        }))
+    );
+}
+
+#[test]
+fn string_pool() {
+    let input = r#"
+fn pick(a: str, b: str, first: bool) -> str {
+    if first {
+        return a;
+    }
+    return b;
+}
+fn main() -> i32 {
+    let s: str = "hi \"there\"\n";
+    let t = pick(s, "hi \"there\"\n", false);
+    let u = "caf\u{e9}\\";
+    t = u;
+    return 0;
+}"#;
+    let asm = compile(input).unwrap();
+    assert!(asm.contains(
+        ".section .rodata\n.Lstr_0:\n.asciz \"hi \\\"there\\\"\\012\"\n.Lstr_1:\n.asciz \"caf\\303\\251\\\\\"\n"
+    ));
+    assert!(!asm.contains(".Lstr_2"));
+    assert!(asm.contains("leaq .Lstr_0(%rip), %rax"));
+    assemble("string_pool", &asm);
+    assert_eq!(run("string_pool", input), 0);
+    assert_eq!(
+        compile(r#"fn main() { let a = "a" == "b"; }"#),
+        Err(CodegenError::Type(TypeError::InvalidOperand {
+            operator: "==",
+            ty: PrimitiveTy::Str
+        }))
+    );
+    assert_eq!(
+        compile(r#"fn main() { let a: str = 1; }"#),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Str,
+            found: PrimitiveTy::Number
+        }))
+    );
+}
+
+#[test]
+fn run_chars() {
+    let input = r"
+fn is_digit(c: char) -> bool {
+    return c >= '0' && c <= '9';
+}
+fn main() -> i32 {
+    let c = 'a';
+    if c != 'a' || c == 'b' || !(c < 'b') {
+        return 1;
+    }
+    if c as u32 != 97 || '\n' as u8 != 10 || '\u{1F600}' as u32 != 128512 {
+        return 2;
+    }
+    let next = (c as u8 + 1) as char;
+    if next != 'b' {
+        return 3;
+    }
+    if 65 as char != 'A' || 'é' as u8 != 233 {
+        return 4;
+    }
+    if !is_digit('7') || is_digit('x') {
+        return 5;
+    }
+    let wide: i64 = -1i8 as i64;
+    if wide != -1 || -1i8 as u32 != 4294967295 || 300i32 as u8 != 44 {
+        return 6;
+    }
+    if true as i32 + false as i32 != 1 || 200u8 as i8 != -56 {
+        return 7;
+    }
+    return 0;
+}";
+    assert_eq!(run("chars", input), 0);
+    assert_eq!(
+        compile("fn main() { let a = 1000 as char; }"),
+        Err(CodegenError::LiteralOutOfRange {
+            value: "1000".to_owned(),
+            ty: PrimitiveTy::Integer(IntegerTy::U8)
+        })
+    );
+    assert_eq!(
+        compile("fn main() { let a = 1i32 as char; }"),
+        Err(CodegenError::Type(TypeError::InvalidCast {
+            from: PrimitiveTy::Integer(IntegerTy::I32),
+            to: PrimitiveTy::Char
+        }))
+    );
+    assert_eq!(
+        compile("fn main() { let a = 'a' + 1; }"),
+        Err(CodegenError::Type(TypeError::Mismatch {
+            expected: PrimitiveTy::Char,
+            found: PrimitiveTy::Number
+        }))
+    );
+}
+
+#[test]
+fn run_integer_bases() {
+    let input = "
+fn main() -> i32 {
+    let mask = 0xFF;
+    if mask != 255 || 0o755 != 493 || 0b1010 != 10 || 1_000_000 != 1000000 {
+        return 1;
+    }
+    let big: u64 = 0xFFFF_FFFF_FFFF_FFFF;
+    if big != 18446744073709551615 {
+        return 2;
+    }
+    let byte = 0x80u8;
+    if byte >> 7 != 1 || -0x80i8 != -128 {
+        return 3;
+    }
+    if 1_0.5_0 != 10.5 {
+        return 4;
+    }
+    return 0;
+}";
+    assert_eq!(run("integer_bases", input), 0);
+    assert_eq!(
+        compile("fn main() { let a: u8 = 0x1_00; }"),
+        Err(CodegenError::LiteralOutOfRange {
+            value: "0x1_00".to_owned(),
+            ty: PrimitiveTy::Integer(IntegerTy::U8)
+        })
+    );
+}
@@ -1,12 +1,15 @@
 use std::rc::Rc;
 
 use deltac::{
-    lexer::tokenizer::Tokenizer,
+    lexer::{
+        token::{SymbolTy, TokenTy},
+        tokenizer::Tokenizer,
+    },
     parse::{
-        BinaryOperationTy, ControlFlow, Expression, Function, Literal, Module, Operation, Parser,
-        Statement,
+        Argument, BinaryOperationTy, Call, ControlFlow, Expected, Expression, Function, Literal,
+        Module, Operation, ParseError, ParsedLiteral, Parser, Statement, Type, UnaryOperationTy,
     },
-    PrimitiveTy,
+    IntegerTy, PrimitiveTy, TextSection,
 };
 
 #[test]
@@ -16,18 +19,20 @@ fn test_expression_1() {
         .collect::<Result<Vec<_>, _>>()
         .unwrap();
     let mut parser = Parser::new(input, &tokens);
-    let expr = parser.parse_expression();
+    let expr = parser.parse_expression().unwrap();
     assert_eq!(
         expr,
         Expression::Operation(Rc::new(Operation::Binary {
             left: Expression::Literal(Literal {
                 value: "12",
                 ty: PrimitiveTy::Number,
+                parsed: Some(ParsedLiteral::Integer(12)),
             }),
             ty: BinaryOperationTy::Addition,
             right: Expression::Literal(Literal {
                 value: "2",
                 ty: PrimitiveTy::Number,
+                parsed: Some(ParsedLiteral::Integer(2)),
             }),
         }))
     );
@@ -40,7 +45,7 @@ fn test_expression_2() {
         .collect::<Result<Vec<_>, _>>()
         .unwrap();
     let mut parser = Parser::new(input, &tokens);
-    let expr = parser.parse_expression();
+    let expr = parser.parse_expression().unwrap();
     assert_eq!(
         expr,
         Expression::Operation(Rc::new(Operation::Binary {
@@ -49,6 +54,7 @@ fn test_expression_2() {
             right: Expression::Literal(Literal {
                 value: "2",
                 ty: PrimitiveTy::Number,
+                parsed: Some(ParsedLiteral::Integer(2)),
             }),
         }))
     );
@@ -67,20 +73,23 @@ fn IAmAFunction() -> int {
         .collect::<Result<Vec<_>, _>>()
         .unwrap();
     let parser = Parser::new(input, &tokens);
-    let expr = parser.parse();
+    let expr = parser.parse().unwrap();
     assert_eq!(
         expr,
         Module {
             functions: vec![Function {
+                docs: vec![],
                 name: "IAmAFunction",
                 arguments: vec![],
-                return_type: Some("int"),
+                return_type: Some(Type::Named("int")),
                 body: vec![
                     Statement::Declaration {
                         name: "a",
+                        ty: None,
                         expression: Expression::Literal(Literal {
                             value: "10",
-                            ty: PrimitiveTy::Number
+                            ty: PrimitiveTy::Number,
+                            parsed: Some(ParsedLiteral::Integer(10))
                         })
                     },
                     Statement::ControlFlow(ControlFlow::If {
@@ -89,7 +98,8 @@ fn IAmAFunction() -> int {
                             ty: BinaryOperationTy::Greater,
                             right: Expression::Literal(Literal {
                                 value: "5",
-                                ty: PrimitiveTy::Number
+                                ty: PrimitiveTy::Number,
+                                parsed: Some(ParsedLiteral::Integer(5))
                             })
                         })),
                         body: vec![Statement::Expression(Expression::Operation(Rc::new(
@@ -98,13 +108,551 @@ fn IAmAFunction() -> int {
                                 ty: BinaryOperationTy::Assignment,
                                 right: Expression::Literal(Literal {
                                     value: "5",
-                                    ty: PrimitiveTy::Number
+                                    ty: PrimitiveTy::Number,
+                                    parsed: Some(ParsedLiteral::Integer(5))
                                 })
                             }
-                        )))]
+                        )))],
+                        else_body: None,
                     })
                 ]
             }]
         }
     );
 }
+
+#[test]
+fn error_unexpected_token() {
+    let input = "fn main() {\n    let = 5;\n}";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let parser = Parser::new(input, &tokens);
+    assert_eq!(
+        parser.parse(),
+        Err(vec![ParseError {
+            section: TextSection {
+                index: 20..21,
+                line: 1..1,
+                column: 8..9,
+            },
+            found: Some(TokenTy::Symbol(SymbolTy::Assign)),
+            expected: vec![Expected::Token(TokenTy::Identifier)],
+            message: "expected identifier, found `=`".to_owned(),
+        }])
+    );
+}
+
+#[test]
+fn error_end_of_input() {
+    let input = "fn main() { let a = 5;";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let parser = Parser::new(input, &tokens);
+    let errors = parser.parse().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].section.index, 22..22);
+    assert_eq!(errors[0].found, None);
+    assert_eq!(
+        errors[0].message,
+        "expected statement or `}`, found end of input"
+    );
+}
+
+#[test]
+fn error_recovery() {
+    let input = "
+fn main() {
+    let a = 5 6;
+    if a > { b = 1; }
+    let = 2;
+    a = 3;
+}";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let parser = Parser::new(input, &tokens);
+    let (module, errors) = parser.parse_recovering();
+    assert_eq!(
+        errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>(),
+        vec![
+            "expected `;`, found `6`",
+            "expected expression, found `{`",
+            "expected identifier, found `=`",
+        ]
+    );
+    assert_eq!(
+        module,
+        Module {
+            functions: vec![Function {
+                docs: vec![],
+                name: "main",
+                arguments: vec![],
+                return_type: None,
+                body: vec![
+                    Statement::Error,
+                    Statement::Error,
+                    Statement::Error,
+                    Statement::Expression(Expression::Operation(Rc::new(Operation::Binary {
+                        left: Expression::Variable("a"),
+                        ty: BinaryOperationTy::Assignment,
+                        right: Expression::Literal(Literal {
+                            value: "3",
+                            ty: PrimitiveTy::Number,
+                            parsed: Some(ParsedLiteral::Integer(3))
+                        })
+                    })))
+                ]
+            }]
+        }
+    );
+}
+
+#[test]
+fn error_recovery_unclosed() {
+    let input = "fn main() { if a > 1 { a = 2;";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let parser = Parser::new(input, &tokens);
+    let (_, errors) = parser.parse_recovering();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].found, None);
+}
+
+#[test]
+fn multiple_functions() {
+    let input = "fn first() {} fn second() -> int {} fn third() { a = 1; }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module
+            .functions
+            .iter()
+            .map(|f| f.name)
+            .collect::<Vec<_>>(),
+        vec!["first", "second", "third"]
+    );
+}
+
+#[test]
+fn error_recovery_items() {
+    let input = "let a = 1; fn broken( fn main() {}";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let (module, errors) = Parser::new(input, &tokens).parse_recovering();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(module.functions.len(), 1);
+    assert_eq!(module.functions[0].name, "main");
+}
+
+fn binary<'a>(left: Expression<'a>, ty: BinaryOperationTy, right: Expression<'a>) -> Expression<'a> {
+    Expression::Operation(Rc::new(Operation::Binary { left, ty, right }))
+}
+
+fn number(value: &str) -> Expression<'_> {
+    Expression::Literal(Literal {
+        value,
+        ty: PrimitiveTy::Number,
+        parsed: Some(ParsedLiteral::Integer(value.parse().unwrap())),
+    })
+}
+
+fn assert_expression(input: &str, expected: Expression) {
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut parser = Parser::new(input, &tokens);
+    assert_eq!(parser.parse_expression().unwrap(), expected);
+}
+
+#[test]
+fn precedence_comparison() {
+    assert_expression(
+        "1 + 2 > 3",
+        binary(
+            binary(number("1"), BinaryOperationTy::Addition, number("2")),
+            BinaryOperationTy::Greater,
+            number("3")
+        )
+    );
+}
+
+#[test]
+fn associativity() {
+    assert_expression(
+        "1 + 2 + 3",
+        binary(
+            binary(number("1"), BinaryOperationTy::Addition, number("2")),
+            BinaryOperationTy::Addition,
+            number("3")
+        )
+    );
+    assert_expression(
+        "a = b = 1 + 2",
+        binary(
+            Expression::Variable("a"),
+            BinaryOperationTy::Assignment,
+            binary(
+                Expression::Variable("b"),
+                BinaryOperationTy::Assignment,
+                binary(number("1"), BinaryOperationTy::Addition, number("2"))
+            )
+        )
+    );
+}
+
+#[test]
+fn precedence_full() {
+    // a || b && -c * 2 + 1 << 3 == d
+    assert_expression(
+        "a || b && -c * 2 + 1 << 3 == d",
+        binary(
+            Expression::Variable("a"),
+            BinaryOperationTy::LogicalOr,
+            binary(
+                Expression::Variable("b"),
+                BinaryOperationTy::LogicalAnd,
+                binary(
+                    binary(
+                        binary(
+                            binary(
+                                Expression::Operation(Rc::new(Operation::Unary {
+                                    target: Expression::Variable("c"),
+                                    ty: UnaryOperationTy::Negate,
+                                })),
+                                BinaryOperationTy::Multiplication,
+                                number("2"),
+                            ),
+                            BinaryOperationTy::Addition,
+                            number("1"),
+                        ),
+                        BinaryOperationTy::ShiftLeft,
+                        number("3"),
+                    ),
+                    BinaryOperationTy::Equal,
+                    Expression::Variable("d"),
+                ),
+            ),
+        ),
+    );
+}
+
+#[test]
+fn parenthesized() {
+    assert_expression(
+        "(1 + 2) * -(a)",
+        binary(
+            binary(number("1"), BinaryOperationTy::Addition, number("2")),
+            BinaryOperationTy::Multiplication,
+            Expression::Operation(Rc::new(Operation::Unary {
+                target: Expression::Variable("a"),
+                ty: UnaryOperationTy::Negate,
+            })),
+        ),
+    );
+}
+
+#[test]
+fn error_unclosed_paren() {
+    let input = "fn main() { let a = (1 + 2; }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let errors = Parser::new(input, &tokens).parse().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "expected `)`, found `;`");
+}
+
+#[test]
+fn function_arguments() {
+    let input = "fn add(a: i32, b: i32,) -> i32 { add(a, 1 + b); }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module,
+        Module {
+            functions: vec![Function {
+                docs: vec![],
+                name: "add",
+                arguments: vec![
+                    Argument { name: "a", ty: Type::Named("i32") },
+                    Argument { name: "b", ty: Type::Named("i32") },
+                ],
+                return_type: Some(Type::Named("i32")),
+                body: vec![Statement::Expression(Expression::Call(Call {
+                    name: "add",
+                    arguments: vec![
+                        Expression::Variable("a"),
+                        binary(number("1"), BinaryOperationTy::Addition, Expression::Variable("b")),
+                    ],
+                }))],
+            }]
+        }
+    );
+}
+
+#[test]
+fn return_statement() {
+    let input = "fn f() -> i32 { return 1 + 2; return; }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module.functions[0].body,
+        vec![
+            Statement::Return(Some(binary(
+                number("1"),
+                BinaryOperationTy::Addition,
+                number("2")
+            ))),
+            Statement::Return(None),
+        ]
+    );
+}
+
+#[test]
+fn else_if_chain() {
+    let input = "fn f() { if a { b = 1; } else if c { b = 2; } else { b = 3; } }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    let assign = |value| {
+        Statement::Expression(binary(
+            Expression::Variable("b"),
+            BinaryOperationTy::Assignment,
+            number(value),
+        ))
+    };
+    assert_eq!(
+        module.functions[0].body,
+        vec![Statement::ControlFlow(ControlFlow::If {
+            condition: Expression::Variable("a"),
+            body: vec![assign("1")],
+            else_body: Some(vec![Statement::ControlFlow(ControlFlow::If {
+                condition: Expression::Variable("c"),
+                body: vec![assign("2")],
+                else_body: Some(vec![assign("3")]),
+            })]),
+        })]
+    );
+}
+
+#[test]
+fn loops() {
+    let input = "fn f() { 'outer: while a { loop { break 'outer; } continue; } }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module.functions[0].body,
+        vec![Statement::ControlFlow(ControlFlow::While {
+            label: Some("outer"),
+            condition: Expression::Variable("a"),
+            body: vec![
+                Statement::ControlFlow(ControlFlow::Loop {
+                    label: None,
+                    body: vec![Statement::ControlFlow(ControlFlow::Break(Some("outer")))],
+                }),
+                Statement::ControlFlow(ControlFlow::Continue(None)),
+            ],
+        })]
+    );
+}
+
+#[test]
+fn for_range() {
+    let input = "fn f() { for i in 0..=n + 1 { continue; } }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module.functions[0].body,
+        vec![Statement::ControlFlow(ControlFlow::For {
+            label: None,
+            variable: "i",
+            start: number("0"),
+            end: binary(
+                Expression::Variable("n"),
+                BinaryOperationTy::Addition,
+                number("1")
+            ),
+            inclusive: true,
+            body: vec![Statement::ControlFlow(ControlFlow::Continue(None))],
+        })]
+    );
+}
+
+#[test]
+fn compound_assignment() {
+    assert_expression(
+        "a += b * 2",
+        Expression::Operation(Rc::new(Operation::CompoundAssignment {
+            target: Expression::Variable("a"),
+            ty: BinaryOperationTy::Addition,
+            value: binary(
+                Expression::Variable("b"),
+                BinaryOperationTy::Multiplication,
+                number("2"),
+            ),
+        })),
+    );
+    assert_expression(
+        "a <<= b = 1",
+        Expression::Operation(Rc::new(Operation::CompoundAssignment {
+            target: Expression::Variable("a"),
+            ty: BinaryOperationTy::ShiftLeft,
+            value: binary(
+                Expression::Variable("b"),
+                BinaryOperationTy::Assignment,
+                number("1"),
+            ),
+        })),
+    );
+}
+
+#[test]
+fn type_annotations() {
+    let input = "fn f(a: bool) -> i32 { let b: i32 = 1; let c = b; }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(
+        module.functions[0].arguments,
+        vec![Argument {
+            name: "a",
+            ty: Type::Named("bool")
+        }]
+    );
+    assert_eq!(module.functions[0].return_type, Some(Type::Named("i32")));
+    assert_eq!(
+        module.functions[0].body,
+        vec![
+            Statement::Declaration {
+                name: "b",
+                ty: Some(Type::Named("i32")),
+                expression: number("1"),
+            },
+            Statement::Declaration {
+                name: "c",
+                ty: None,
+                expression: Expression::Variable("b"),
+            },
+        ]
+    );
+}
+
+#[test]
+fn error_missing_type() {
+    let input = "fn f() { let a: = 1; }";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let errors = Parser::new(input, &tokens).parse().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].expected, vec![Expected::Type]);
+    assert_eq!(errors[0].message, "expected type, found `=`");
+}
+
+#[test]
+fn casts() {
+    let cast = |target, name| {
+        Expression::Operation(Rc::new(Operation::Cast {
+            target,
+            ty: Type::Named(name),
+        }))
+    };
+    assert_expression(
+        "a + b as i64 * 2",
+        binary(
+            Expression::Variable("a"),
+            BinaryOperationTy::Addition,
+            binary(
+                cast(Expression::Variable("b"), "i64"),
+                BinaryOperationTy::Multiplication,
+                number("2"),
+            ),
+        ),
+    );
+    assert_expression(
+        "-c as u8 as char",
+        cast(
+            cast(
+                Expression::Operation(Rc::new(Operation::Unary {
+                    target: Expression::Variable("c"),
+                    ty: UnaryOperationTy::Negate,
+                })),
+                "u8",
+            ),
+            "char",
+        ),
+    );
+}
+
+#[test]
+fn literal_values() {
+    assert_expression(
+        r#""a\tb""#,
+        Expression::Literal(Literal {
+            value: r#""a\tb""#,
+            ty: PrimitiveTy::Str,
+            parsed: Some(ParsedLiteral::Text("a\tb".to_owned())),
+        }),
+    );
+    assert_expression(
+        "0x1_fu8",
+        Expression::Literal(Literal {
+            value: "0x1_fu8",
+            ty: PrimitiveTy::Integer(IntegerTy::U8),
+            parsed: Some(ParsedLiteral::Integer(31)),
+        }),
+    );
+    assert_expression(
+        "true",
+        Expression::Literal(Literal {
+            value: "true",
+            ty: PrimitiveTy::Boolean,
+            parsed: None,
+        }),
+    );
+}
+
+#[test]
+fn doc_comments() {
+    let input = "
+## Adds one.
+##
+##Returns `a + 1`.
+fn inc(a: i32) -> i32 {
+    ## Not attached to anything.
+    return a + 1;
+}
+# Not a doc comment.
+fn main() {}";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let module = Parser::new(input, &tokens).parse().unwrap();
+    assert_eq!(module.functions[0].docs, vec!["Adds one.", "", "Returns `a + 1`."]);
+    assert_eq!(module.functions[0].body.len(), 1);
+    assert!(module.functions[1].docs.is_empty());
+
+    let input = "fn main() {}\n## Dangling.";
+    let tokens = Tokenizer::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let errors = Parser::new(input, &tokens).parse().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "expected `fn`, found end of input");
+}
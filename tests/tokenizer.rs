@@ -1,9 +1,11 @@
+use std::ops::Range;
+
 use deltac::{
     lexer::{
-        token::{KeywordTy, SymbolTy, Token, TokenTy},
-        tokenizer::Tokenizer,
+        token::{KeywordTy, SymbolTy, Token, TokenTy, SYMBOLS},
+        tokenizer::{parse_integer, unescape, LexError, LexErrorTy, Tokenizer},
     },
-    PrimitiveTy, TextSection,
+    FloatTy, IntegerTy, PrimitiveTy, TextSection,
 };
 
 #[test]
@@ -238,28 +240,562 @@ fn basic_invalid_1() {
     assert_eq!(
         v.as_slice(),
         &[
-            Err(TextSection {
-                index: 0..2,
-                line: 0..0,
-                column: 0..2
+            Err(LexError {
+                section: TextSection {
+                    index: 0..4,
+                    line: 0..0,
+                    column: 0..2
+                },
+                ty: LexErrorTy::InvalidCharacters
             }),
             Ok(Token {
-                range: 3..5,
+                range: 5..7,
                 ty: TokenTy::Literal(PrimitiveTy::Number)
             }),
             Ok(Token {
-                range: 6..7,
+                range: 8..9,
                 ty: TokenTy::Symbol(SymbolTy::Add)
             }),
             Ok(Token {
-                range: 8..9,
+                range: 10..11,
                 ty: TokenTy::Literal(PrimitiveTy::Number)
             }),
-            Err(TextSection {
-                index: 10..12,
-                line: 0..0,
-                column: 10..12
+            Err(LexError {
+                section: TextSection {
+                    index: 12..16,
+                    line: 0..0,
+                    column: 10..12
+                },
+                ty: LexErrorTy::InvalidCharacters
             })
         ]
     )
 }
+
+#[test]
+fn operators_1() {
+    let input = "a<=b<<c!=!d&&e||f%g";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::LessEqual),
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::ShiftLeft),
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::NotEqual),
+            TokenTy::Symbol(SymbolTy::Not),
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::LogicalAnd),
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::LogicalOr),
+            TokenTy::Identifier,
+            TokenTy::Symbol(SymbolTy::Rem),
+            TokenTy::Identifier,
+        ]
+    )
+}
+
+#[test]
+fn loop_keywords_1() {
+    let input = "'outer: while loop break 'outer continue";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Label,
+            TokenTy::Symbol(SymbolTy::Colon),
+            TokenTy::Keyword(KeywordTy::While),
+            TokenTy::Keyword(KeywordTy::Loop),
+            TokenTy::Keyword(KeywordTy::Break),
+            TokenTy::Label,
+            TokenTy::Keyword(KeywordTy::Continue),
+        ]
+    )
+}
+
+#[test]
+fn boolean_literals_1() {
+    let input = "true false trueish";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Literal(PrimitiveTy::Boolean),
+            TokenTy::Literal(PrimitiveTy::Boolean),
+            TokenTy::Identifier,
+        ]
+    )
+}
+
+#[test]
+fn keyword_prefixes_1() {
+    let input = "letter iffy fnord int returned let if fn in";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Identifier,
+            TokenTy::Identifier,
+            TokenTy::Identifier,
+            TokenTy::Identifier,
+            TokenTy::Identifier,
+            TokenTy::Keyword(KeywordTy::Let),
+            TokenTy::Keyword(KeywordTy::If),
+            TokenTy::Keyword(KeywordTy::Function),
+            TokenTy::Keyword(KeywordTy::In),
+        ]
+    )
+}
+
+#[test]
+fn keyword_prefixes_2() {
+    let v: Vec<Token> = Tokenizer::new("let letter=fnord;")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        v,
+        vec![
+            Token {
+                range: 0..3,
+                ty: TokenTy::Keyword(KeywordTy::Let)
+            },
+            Token {
+                range: 4..10,
+                ty: TokenTy::Identifier
+            },
+            Token {
+                range: 10..11,
+                ty: TokenTy::Symbol(SymbolTy::Assign)
+            },
+            Token {
+                range: 11..16,
+                ty: TokenTy::Identifier
+            },
+            Token {
+                range: 16..17,
+                ty: TokenTy::Symbol(SymbolTy::EndStmt)
+            },
+        ]
+    )
+}
+
+#[test]
+fn symbols_longest_match_1() {
+    let input = "a<<=b>>=c..=d..e::f=>g+=h->i";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .filter(|ty| *ty != TokenTy::Identifier)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Symbol(SymbolTy::ShiftLeftAssign),
+            TokenTy::Symbol(SymbolTy::ShiftRightAssign),
+            TokenTy::Symbol(SymbolTy::RangeInclusive),
+            TokenTy::Symbol(SymbolTy::Range),
+            TokenTy::Symbol(SymbolTy::PathSep),
+            TokenTy::Symbol(SymbolTy::FatArrow),
+            TokenTy::Symbol(SymbolTy::AddAssign),
+            TokenTy::Symbol(SymbolTy::ThinArrow),
+        ]
+    )
+}
+
+#[test]
+fn symbols_longest_match_2() {
+    // Every symbol must lex as itself rather than a sequence of shorter ones.
+    for (text, ty) in SYMBOLS.iter() {
+        let v: Vec<Token> = Tokenizer::new(text).collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            v,
+            vec![Token {
+                range: 0..text.len(),
+                ty: TokenTy::Symbol(*ty)
+            }],
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn integer_suffixes_1() {
+    let input = "10u8 7i64 3usize 5abc";
+    let v: Vec<TokenTy> = Tokenizer::new(input)
+        .map(|t| t.unwrap().ty)
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            TokenTy::Literal(PrimitiveTy::Integer(IntegerTy::U8)),
+            TokenTy::Literal(PrimitiveTy::Integer(IntegerTy::I64)),
+            TokenTy::Literal(PrimitiveTy::Integer(IntegerTy::Usize)),
+            TokenTy::Literal(PrimitiveTy::Number),
+            TokenTy::Identifier,
+        ]
+    )
+}
+
+#[test]
+fn float_literals_1() {
+    let input = "1.5 2e10 1.5e-3f32 3f64 0..n 1e";
+    let v: Vec<(TokenTy, &str)> = Tokenizer::new(input)
+        .map(|t| t.unwrap())
+        .map(|t| (t.ty, &input[t.range]))
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            (TokenTy::Literal(PrimitiveTy::Decimal), "1.5"),
+            (TokenTy::Literal(PrimitiveTy::Decimal), "2e10"),
+            (TokenTy::Literal(PrimitiveTy::Float(FloatTy::F32)), "1.5e-3f32"),
+            (TokenTy::Literal(PrimitiveTy::Float(FloatTy::F64)), "3f64"),
+            (TokenTy::Literal(PrimitiveTy::Number), "0"),
+            (TokenTy::Symbol(SymbolTy::Range), ".."),
+            (TokenTy::Identifier, "n"),
+            (TokenTy::Literal(PrimitiveTy::Number), "1"),
+            (TokenTy::Identifier, "e"),
+        ]
+    )
+}
+
+#[test]
+fn string_literals_1() {
+    let input = r#"let s = "a \"b\" \\ c\n\t\u{1F600}" + "";"#;
+    let v: Vec<(TokenTy, &str)> = Tokenizer::new(input)
+        .map(|t| t.unwrap())
+        .map(|t| (t.ty, &input[t.range]))
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            (TokenTy::Keyword(KeywordTy::Let), "let"),
+            (TokenTy::Identifier, "s"),
+            (TokenTy::Symbol(SymbolTy::Assign), "="),
+            (
+                TokenTy::Literal(PrimitiveTy::Str),
+                r#""a \"b\" \\ c\n\t\u{1F600}""#
+            ),
+            (TokenTy::Symbol(SymbolTy::Add), "+"),
+            (TokenTy::Literal(PrimitiveTy::Str), r#""""#),
+            (TokenTy::Symbol(SymbolTy::EndStmt), ";"),
+        ]
+    );
+    assert_eq!(
+        unescape(r#"a \"b\" \\ c\n\t\u{1F600}"#),
+        Ok("a \"b\" \\ c\n\t\u{1F600}".to_owned())
+    );
+}
+
+#[test]
+fn string_errors_1() {
+    let v: Vec<_> = Tokenizer::new(r#"x "a\qb" "\u{110000}" y"#).collect();
+    assert_eq!(
+        v,
+        vec![
+            Ok(Token {
+                range: 0..1,
+                ty: TokenTy::Identifier
+            }),
+            Err(LexError {
+                section: TextSection {
+                    index: 4..6,
+                    line: 0..0,
+                    column: 4..6
+                },
+                ty: LexErrorTy::InvalidEscape
+            }),
+            Err(LexError {
+                section: TextSection {
+                    index: 10..20,
+                    line: 0..0,
+                    column: 10..20
+                },
+                ty: LexErrorTy::InvalidEscape
+            }),
+            Ok(Token {
+                range: 22..23,
+                ty: TokenTy::Identifier
+            }),
+        ]
+    );
+    let v: Vec<_> = Tokenizer::new("x\n\"abc").collect();
+    assert_eq!(
+        v[1],
+        Err(LexError {
+            section: TextSection {
+                index: 2..6,
+                line: 1..1,
+                column: 0..4
+            },
+            ty: LexErrorTy::UnterminatedString
+        })
+    );
+}
+
+#[test]
+fn char_literals_1() {
+    let input = r"'a' '\n' '\'' '\u{1F600}' 'é' 'outer: 'a";
+    let v: Vec<(TokenTy, &str)> = Tokenizer::new(input)
+        .map(|t| t.unwrap())
+        .map(|t| (t.ty, &input[t.range]))
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            (TokenTy::Literal(PrimitiveTy::Char), "'a'"),
+            (TokenTy::Literal(PrimitiveTy::Char), r"'\n'"),
+            (TokenTy::Literal(PrimitiveTy::Char), r"'\''"),
+            (TokenTy::Literal(PrimitiveTy::Char), r"'\u{1F600}'"),
+            (TokenTy::Literal(PrimitiveTy::Char), "'é'"),
+            (TokenTy::Label, "'outer"),
+            (TokenTy::Symbol(SymbolTy::Colon), ":"),
+            (TokenTy::Label, "'a"),
+        ]
+    );
+    let errors: Vec<LexErrorTy> = Tokenizer::new(r"'\q' '\n\n' '\n")
+        .map(|t| t.unwrap_err().ty)
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            LexErrorTy::InvalidEscape,
+            LexErrorTy::InvalidChar,
+            LexErrorTy::UnterminatedChar,
+        ]
+    );
+}
+
+#[test]
+fn integer_bases_1() {
+    let input = "0xFF 0o755 0b1010 1_000_000 0xffu8 0b1i64 1_0.5_0 0x1f32";
+    let v: Vec<(TokenTy, &str)> = Tokenizer::new(input)
+        .map(|t| t.unwrap())
+        .map(|t| (t.ty, &input[t.range]))
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            (TokenTy::Literal(PrimitiveTy::Number), "0xFF"),
+            (TokenTy::Literal(PrimitiveTy::Number), "0o755"),
+            (TokenTy::Literal(PrimitiveTy::Number), "0b1010"),
+            (TokenTy::Literal(PrimitiveTy::Number), "1_000_000"),
+            (TokenTy::Literal(PrimitiveTy::Integer(IntegerTy::U8)), "0xffu8"),
+            (TokenTy::Literal(PrimitiveTy::Integer(IntegerTy::I64)), "0b1i64"),
+            (TokenTy::Literal(PrimitiveTy::Decimal), "1_0.5_0"),
+            (TokenTy::Literal(PrimitiveTy::Number), "0x1f32"),
+        ]
+    );
+    let values: Vec<_> = ["0xFF", "0o755", "0b1010", "1_000_000", "0xffu8", "0x1f32"]
+        .into_iter()
+        .map(parse_integer)
+        .collect();
+    assert_eq!(values, vec![Ok(255), Ok(493), Ok(10), Ok(1_000_000), Ok(255), Ok(0x1f32)]);
+}
+
+#[test]
+fn integer_errors_1() {
+    let v: Vec<_> = Tokenizer::new("0b102 + 0o8 + 0x + 0xFFux + 18446744073709551616").collect();
+    let errors: Vec<_> = v.into_iter().filter_map(Result::err).collect();
+    assert_eq!(
+        errors,
+        vec![
+            LexError {
+                section: TextSection {
+                    index: 0..5,
+                    line: 0..0,
+                    column: 0..5
+                },
+                ty: LexErrorTy::InvalidDigit
+            },
+            LexError {
+                section: TextSection {
+                    index: 8..11,
+                    line: 0..0,
+                    column: 8..11
+                },
+                ty: LexErrorTy::InvalidDigit
+            },
+            LexError {
+                section: TextSection {
+                    index: 14..16,
+                    line: 0..0,
+                    column: 14..16
+                },
+                ty: LexErrorTy::MissingDigits
+            },
+            LexError {
+                section: TextSection {
+                    index: 19..25,
+                    line: 0..0,
+                    column: 19..25
+                },
+                ty: LexErrorTy::InvalidDigit
+            },
+            LexError {
+                section: TextSection {
+                    index: 28..48,
+                    line: 0..0,
+                    column: 28..48
+                },
+                ty: LexErrorTy::IntegerOverflow
+            },
+        ]
+    );
+    assert_eq!(parse_integer("18446744073709551615"), Ok(u64::MAX));
+}
+
+#[test]
+fn block_comments_1() {
+    let input = "a /* one /* nested */ still */ b # c\n## doc\r\nd /* open";
+    let v: Vec<_> = Tokenizer::new(input)
+        .map(|t| t.map(|t| (t.ty, &input[t.range])))
+        .collect();
+    assert_eq!(
+        v,
+        vec![
+            Ok((TokenTy::Identifier, "a")),
+            Ok((TokenTy::Identifier, "b")),
+            Ok((TokenTy::DocComment, "## doc")),
+            Ok((TokenTy::Identifier, "d")),
+            Err(LexError {
+                section: TextSection {
+                    index: 47..54,
+                    line: 2..2,
+                    column: 2..9
+                },
+                ty: LexErrorTy::UnterminatedComment
+            }),
+        ]
+    );
+}
+
+#[test]
+fn positions_after_comments_1() {
+    let input = "# comment\na /* x\ny */ b ## doc\nc";
+    let v: Vec<(Range<usize>, usize, usize)> = Tokenizer::new(input)
+        .map(|t| t.unwrap().range)
+        .map(|range| {
+            let section = TextSection::from_index(input, range.clone());
+            (range, section.line.start, section.column.start)
+        })
+        .collect();
+    assert_eq!(
+        v,
+        vec![(10..11, 1, 0), (22..23, 2, 5), (24..30, 2, 7), (31..32, 3, 0)]
+    );
+}
+
+#[test]
+fn positions_crlf_1() {
+    let input = "let a = 1;\r\n# note\r\nlet b = ¤;\r\n";
+    let v: Vec<_> = Tokenizer::new(input).collect();
+    assert_eq!(
+        v[5],
+        Ok(Token {
+            range: 20..23,
+            ty: TokenTy::Keyword(KeywordTy::Let)
+        })
+    );
+    assert_eq!(
+        v[8],
+        Err(LexError {
+            section: TextSection {
+                index: 28..30,
+                line: 2..2,
+                column: 8..9
+            },
+            ty: LexErrorTy::InvalidCharacters
+        })
+    );
+    // The `\r` ending the comment doesn't take up a column.
+    assert_eq!(
+        TextSection::from_index(input, 18..19),
+        TextSection {
+            index: 18..19,
+            line: 1..1,
+            column: 6..6
+        }
+    );
+}
+
+#[test]
+fn positions_crlf_2() {
+    // Only a `\r` ending a line is left out of the columns.
+    let input = "a\rb\r";
+    assert_eq!(
+        TextSection::from_index(input, 2..4),
+        TextSection {
+            index: 2..4,
+            line: 0..0,
+            column: 2..3
+        }
+    );
+}
+
+#[test]
+fn positions_unicode_1() {
+    let input = "héllo + 😀x ¤ wörld";
+    let mut tokenizer = Tokenizer::new(input);
+    let first = tokenizer.next().unwrap().unwrap();
+    assert_eq!(&input[first.range.clone()], "héllo");
+    assert_eq!(tokenizer.next().unwrap().unwrap().range, 7..8);
+    // `😀` is two UTF-16 code units wide.
+    assert_eq!(
+        tokenizer.next().unwrap(),
+        Err(LexError {
+            section: TextSection {
+                index: 9..13,
+                line: 0..0,
+                column: 8..10
+            },
+            ty: LexErrorTy::InvalidCharacters
+        })
+    );
+    assert_eq!((tokenizer.line, tokenizer.column, tokenizer.index), (0, 10, 13));
+    let rest: Vec<&str> = tokenizer
+        .filter_map(Result::ok)
+        .map(|t| &input[t.range])
+        .collect();
+    assert_eq!(rest, vec!["x", "wörld"]);
+}
+
+#[test]
+fn char_literals_2() {
+    let v: Vec<_> = Tokenizer::new("'ab' '' x").collect();
+    assert_eq!(
+        v,
+        vec![
+            Err(LexError {
+                section: TextSection {
+                    index: 0..4,
+                    line: 0..0,
+                    column: 0..4
+                },
+                ty: LexErrorTy::InvalidChar
+            }),
+            Err(LexError {
+                section: TextSection {
+                    index: 5..7,
+                    line: 0..0,
+                    column: 5..7
+                },
+                ty: LexErrorTy::InvalidChar
+            }),
+            Ok(Token {
+                range: 8..9,
+                ty: TokenTy::Identifier
+            }),
+        ]
+    );
+}